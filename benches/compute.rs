@@ -0,0 +1,103 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use git2::{Repository, RepositoryInitOptions, Signature};
+use git_semversion::Options;
+use tempfile::TempDir;
+
+/// Build a repository of a first-parent chain of commits, tagging every `tag_every`th commit from the root with an
+/// increasing version and a tag which is not semver, and leaving HEAD untagged.
+fn repository(commits: u64, tag_every: Option<u64>) -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repository = Repository::init_opts(
+        dir.path(),
+        RepositoryInitOptions::new().initial_head("main"),
+    )
+    .unwrap();
+    {
+        let signature = Signature::now("Bench", "bench@example.com").unwrap();
+        let tree_id = repository.index().unwrap().write_tree().unwrap();
+        let tree = repository.find_tree(tree_id).unwrap();
+        let mut parent = None;
+        for n in 0..commits {
+            let parents = parent.iter().collect::<Vec<_>>();
+            let oid = repository
+                .commit(Some("HEAD"), &signature, &signature, "Fix", &tree, &parents)
+                .unwrap();
+            let commit = repository.find_commit(oid).unwrap();
+            if tag_every.is_some_and(|every| n % every == 0 && n + 1 < commits) {
+                repository
+                    .tag_lightweight(&format!("1.0.{n}"), commit.as_object(), false)
+                    .unwrap();
+                repository
+                    .tag(
+                        &format!("build-{n}"),
+                        commit.as_object(),
+                        &signature,
+                        "Build",
+                        false,
+                    )
+                    .unwrap();
+            }
+            parent = Some(commit);
+        }
+    }
+    (dir, repository)
+}
+
+/// Read a repository size from an environment variable, so larger histories can be benched without editing this file,
+/// falling back to a default small enough to run in a few minutes.
+fn size(var: &str, default: u64) -> u64 {
+    std::env::var(var)
+        .map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("{var} is not a number: {value}"))
+        })
+        .unwrap_or(default)
+}
+
+/// The number of commits in each benched repository, from GIT_SEMVER_BENCH_COMMITS.
+fn commits() -> u64 {
+    size("GIT_SEMVER_BENCH_COMMITS", 5_000)
+}
+
+/// How many commits apart the sparsely tagged repository is tagged, from GIT_SEMVER_BENCH_TAG_EVERY.
+fn tag_every() -> u64 {
+    size("GIT_SEMVER_BENCH_TAG_EVERY", 100)
+}
+
+fn bench_compute(c: &mut Criterion) {
+    let options = Options::default();
+    let commits = commits();
+    let mut group = c.benchmark_group("compute");
+    group.sample_size(20);
+    for (name, commits, tag_every) in [
+        (format!("{commits} commits no tags"), commits, None),
+        (format!("{commits} tags"), commits + 1, Some(1)),
+        (
+            format!("{commits} commits sparse tags"),
+            commits,
+            Some(tag_every()),
+        ),
+    ] {
+        let (_dir, repository) = repository(commits, tag_every);
+        group.bench_function(name, |b| {
+            b.iter(|| git_semversion::compute(&repository, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_reachable_versions(c: &mut Criterion) {
+    let options = Options::default();
+    let commits = commits();
+    let (_dir, repository) = repository(commits + 1, Some(1));
+    let mut group = c.benchmark_group("reachable_versions");
+    group.sample_size(20);
+    group.bench_function(format!("{commits} tags"), |b| {
+        b.iter(|| git_semversion::reachable_versions(&repository, "HEAD", &options).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_compute, bench_reachable_versions);
+criterion_main!(benches);
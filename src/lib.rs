@@ -0,0 +1,3068 @@
+//! Generate a semantic versioning compliant tag for your HEAD commit.
+
+use std::{
+    char,
+    collections::{HashMap, VecDeque},
+    env, error,
+    fmt::{Debug, Display},
+    io,
+    path::Path,
+    process::{self, Stdio},
+};
+
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser, TypedValueParser},
+    Args, Parser, ValueEnum,
+};
+use git2::{
+    BranchType, Commit, Cred, CredentialType, ErrorCode, ObjectType, Oid, PushOptions, Reference,
+    RemoteCallbacks, Repository, RepositoryOpenFlags, Sort, Status, StatusOptions,
+};
+use regex::{Captures, Regex};
+use semver_extra::{
+    semver::{BuildMetadata, Prerelease, Version},
+    Increment, IncrementLevel,
+};
+use serde::{Serialize, Serializer};
+
+const INCREMENT_LEVEL_CAPTURE_NAME: &str = "level";
+
+const INCREMENT_LABEL_PREFIX: &str = "semver:";
+
+const VERSION_PLACEHOLDER: &str = "{version}";
+
+/// The remote whose remote-tracking branches stand in for missing local main branches.
+const DEFAULT_REMOTE: &str = "origin";
+
+const COMPONENT_PLACEHOLDER: &str = "{component}";
+
+#[derive(Clone, Debug, Args, Serialize)]
+/// Options controlling how the version is computed.
+pub struct Options {
+    /// The name of your repository's main branch. Useful if you continue to use "master" or "trunk".
+    #[arg(short, long, default_value = "main")]
+    pub main_branch: Vec<String>,
+
+    /// Commit standing in for the tip of the main branch, eg. the base commit of a pull request as given by CI, bypassing --main-branch entirely. Must resolve to a commit in the repository.
+    #[arg(long, value_name = "REVISION")]
+    pub main_commit: Option<String>,
+
+    /// Consider HEAD on the main branch also when the main branch's tip is one of HEAD's first-parent ancestors, ie. HEAD is ahead of it, eg. to preview the version of local commits before they are pushed to the main branch.
+    #[arg(long)]
+    pub treat_ahead_of_main_as_main: bool,
+
+    /// Off the main branch, find the base version from the merge base of HEAD and the main branch, ie. where the branch forked off it, and count for the prerelease only the commits unique to the branch since, ignoring tags made on the branch itself.
+    #[arg(long)]
+    pub relative_to_main: bool,
+
+    /// Identifier to use for prerelease during non-main branch execution, using branch name slug when omitted.
+    #[arg(short, long)]
+    pub prerelease_id: Option<String>,
+
+    /// Reference whose name to slug as the prerelease identifier when --prerelease-id is omitted, eg. the source branch of a pull request in a detached checkout, in place of HEAD's branch name.
+    #[arg(long, value_name = "REF")]
+    pub prerelease_id_ref: Option<String>,
+
+    /// Revision to use for prerelease during non-main branch execution, using the shortest unambiguous commit hash when omitted.
+    #[arg(short = 'r', long)]
+    pub prerelease_revision: Option<String>,
+
+    /// Template of the prerelease during non-main branch execution, in which "{id}", "{revision}", "{count}" and "{matched_count}" are substituted, eg. "{id}.{count}.{revision}".
+    #[arg(long, value_name = "TEMPLATE", default_value = "{id}.{revision}")]
+    pub prerelease_format: String,
+
+    /// Strip leading zeros from numeric identifiers of the prerelease, eg. "rc.01" becoming "rc.1", rather than failing since semver forbids them.
+    #[arg(long)]
+    pub trim_leading_zeros: bool,
+
+    /// Characters besides alphanumerics to keep as they are when slugging the prerelease identifier, eg. "." for "release/1.2" to become "release-1.2" rather than "release-1-2". The result must still be a valid semver prerelease.
+    #[arg(long, value_name = "CHARS")]
+    pub slug_allow: Option<String>,
+
+    /// Use the prerelease identifier as given, or HEAD's branch name as is, rather than slugging it, eg. to keep "rc1.2" from becoming "rc1-2". Fails if it is not a valid semver prerelease.
+    #[arg(long)]
+    pub no_slug: bool,
+
+    /// Explicit increment level override for use during main branch execution, forcing to ignore the increment level derived from commit summary.
+    #[arg(short, long, ignore_case = true, value_parser = increment_level_parser())]
+    #[serde(serialize_with = "serialize_forced_increment")]
+    pub increment: Option<IncrementLevel>,
+
+    /// Environment variable holding pull request labels, as a JSON array or comma separated list, from which a "semver:<level>" label forces the increment level like --increment does. The explicit --increment takes precedence.
+    #[arg(long, value_name = "VAR")]
+    pub increment_from_env: Option<String>,
+
+    /// Environment variable holding the pull request title, matched against --match-expression to force the increment level like --increment does.
+    #[arg(long, value_name = "VAR")]
+    pub bump_from_title_env: Option<String>,
+
+    /// Path of a file in the HEAD commit's tree declaring the increment level of a release from the main branch, eg. ".version-bump" holding "minor", or "none" to leave the version as is.
+    #[arg(long, value_name = "PATH")]
+    pub bump_file: Option<String>,
+
+    /// Least increment level of a release from the main branch, raising any lower increment level derived or given to it, eg. "minor" for release trains which always move the minor version.
+    #[arg(long, ignore_case = true, value_parser = increment_level_parser())]
+    #[serde(serialize_with = "serialize_forced_increment")]
+    pub min_increment: Option<IncrementLevel>,
+
+    /// Increment level override for non-merge commits to main branch, ie. commits directly to main branch, or "none" to leave the version as is.
+    #[arg(long, default_value = "patch", ignore_case = true, value_parser = default_increment_parser())]
+    pub default_increment: DefaultIncrement,
+
+    /// Leave the version as is when no increment level is found for the HEAD commit on the main branch, rather than failing for a merge commit whose summary does not match --match-expression or applying --default-increment to a commit directly to main branch.
+    #[arg(long)]
+    pub no_default_head_bump: bool,
+
+    /// Leave the version at 0.0.0 on the main branch until a version tag is reachable, rather than incrementing it as the HEAD commit says.
+    #[arg(long)]
+    pub no_implicit_initial_bump: bool,
+
+    /// What to do when the HEAD commit on the main branch has an empty summary, either treat it as any other summary not matching --match-expression, leave the version as is, or fail. The --increment and --trailer overrides still apply.
+    #[arg(long, value_enum, default_value_t = OnEmptySummary::Default)]
+    pub on_empty_summary: OnEmptySummary,
+
+    /// When HEAD is already tagged, take its tag as the base and compute the version following it rather than failing.
+    #[arg(long)]
+    pub next: bool,
+
+    /// When HEAD is already tagged, give its version a prerelease as for a branch other than the main branch rather than failing, eg. "1.2.3-main.<revision>" for a candidate artifact built from a release commit.
+    #[arg(long, conflicts_with = "next")]
+    pub prerelease_even_if_tagged: bool,
+
+    /// Regular expression to match the increment level in the commit summary of a commit to the main branch. The increment level is read from the capture group named "level" if present, otherwise from the first capture group, and may be any name --increment accepts, eg. "feat" or "fix".
+    #[arg(
+        short = 'e',
+        long,
+        default_value = r"^Merge .*(patch|minor|major)/[\w-]+"
+    )]
+    pub match_expression: String,
+
+    /// Increment levels for the prefixes of merged branches, eg. "feature=minor,bugfix=patch,hotfix=patch,breaking=major", by which a merge commit to the main branch whose summary names a branch such as "feature/thing" is incremented, taking precedence over --match-expression.
+    #[arg(long, value_name = "PREFIX=LEVEL", value_delimiter = ',', value_parser = parse_branch_prefix)]
+    pub branch_prefix_map: Vec<BranchPrefix>,
+
+    /// Scan the whole commit message for every match of --match-expression, taking the greatest increment level among them, rather than the first match in the summary.
+    #[arg(long)]
+    pub highest_match: bool,
+
+    /// Which commits decide the increment of a release, either the HEAD commit alone or the highest level named by any first-parent commit since the base version, eg. a single minor bump for five patch merges and one minor merge.
+    #[arg(long, value_enum, default_value_t = IncrementPolicy::Head)]
+    pub increment_policy: IncrementPolicy,
+
+    /// Glob pattern, as for --exclude-tag-pattern, which the author name or email of a commit since the base version must match for it to be counted, eg. "*@example.com" to leave out commits vendored from upstream.
+    #[arg(long, value_name = "PATTERN")]
+    pub only_author: Option<String>,
+
+    /// Key of a commit message trailer, eg. "Bump" for "Bump: minor", from which to read the increment level of a commit to the main branch, taking precedence over --match-expression and --default-increment. Trailer values which are not increment levels are disregarded.
+    #[arg(long, value_name = "KEY")]
+    pub trailer: Option<String>,
+
+    /// Shell command deciding the increment level of the HEAD commit on the main branch, given the commit id and summary as its arguments and printing an increment level or "none".
+    #[arg(long, value_name = "COMMAND")]
+    pub resolver_cmd: Option<String>,
+
+    /// Build metadata to append to the version, eg. "build" or "sha.abc1234". "{build_timestamp}" is substituted with HEAD's commit time, as chosen by --time-source, in UTC as YYYYMMDDHHMMSS, eg. "ts.{build_timestamp}", for build metadata which sorts chronologically.
+    #[arg(short, long)]
+    pub build_metadata: Option<String>,
+
+    /// Which time of HEAD "{build_timestamp}" is taken from, either when it was committed or when it was authored, eg. to keep the timestamp of a commit across rebases.
+    #[arg(long, value_enum, default_value_t = TimeSource::Committer)]
+    pub time_source: TimeSource,
+
+    /// Environment variable holding a build number, eg. GITHUB_RUN_NUMBER, to append to the version as build metadata after any --build-metadata. Characters not allowed in build metadata are replaced with "-".
+    #[arg(long, value_name = "VAR")]
+    pub build_from_env: Option<String>,
+
+    /// Glob pattern, where "*" matches any run of characters and "?" any single character, of tags to disregard even if they are valid semver, eg. "*-nightly". May be given more than once.
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude_tag_pattern: Vec<String>,
+
+    /// Disregard tags whose signature "git verify-tag" does not accept, ie. lightweight tags, unsigned annotated tags and tags signed with an untrusted or unknown key. Requires git on the PATH, configured with the keys to verify against, eg. through gpg.program.
+    #[arg(long)]
+    pub require_signed_tags: bool,
+
+    /// Template of the names of version tags, with "{version}" standing for the version and "{component}" for --component, eg. "{component}@{version}" for monorepo tags such as "packages/api@1.2.3". Tags not matching it are disregarded, and tags are created named after it.
+    #[arg(long, value_name = "TEMPLATE", default_value = VERSION_PLACEHOLDER)]
+    pub tag_template: String,
+
+    /// Component substituted for "{component}" in --tag-template, eg. "packages/api".
+    #[arg(long)]
+    pub component: Option<String>,
+
+    /// Which tags may serve as the base version, either any semver tag or only those without a prerelease.
+    #[arg(long, value_enum, default_value_t = Base::Any)]
+    pub base: Base,
+
+    /// Greatest version which may be computed, eg. "1.999.999" to never reach 2.0.0 automatically. Build metadata is disregarded in the comparison.
+    #[arg(long, value_name = "VERSION")]
+    pub max_version: Option<Version>,
+
+    /// What to do when the computed version exceeds --max-version, either fail or clamp it to the ceiling.
+    #[arg(long, value_enum, default_value_t = OnCeiling::Error)]
+    pub on_ceiling: OnCeiling,
+
+    /// First-parent ancestor of HEAD, eg. a tag several releases back, to list the changelog of --changelog and "{changelog}" from instead of the base version. The version is still computed from the base version.
+    #[arg(long, value_name = "REVISION")]
+    pub changelog_since: Option<String>,
+}
+
+/// Names accepted for each increment level on the command line, canonical name first.
+const INCREMENT_LEVEL_NAMES: [(IncrementLevel, &[&str]); 3] = [
+    (IncrementLevel::Patch, &["patch", "fix", "bugfix"]),
+    (IncrementLevel::Minor, &["minor", "feature", "feat"]),
+    (IncrementLevel::Major, &["major", "breaking"]),
+];
+
+/// Parse an increment level case-insensitively, also accepting the aliases "fix" and "bugfix" for patch, "feature" and
+/// "feat" for minor, and "breaking" for major.
+pub fn parse_increment_level(name: &str) -> Option<IncrementLevel> {
+    INCREMENT_LEVEL_NAMES
+        .iter()
+        .find(|(_, names)| names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        .map(|(level, _)| *level)
+}
+
+fn increment_level_parser() -> impl TypedValueParser<Value = IncrementLevel> {
+    PossibleValuesParser::new(
+        INCREMENT_LEVEL_NAMES
+            .map(|(_, names)| PossibleValue::new(names[0]).aliases(names[1..].iter().copied())),
+    )
+    .map(|name| parse_increment_level(&name).expect("possible values are increment level names"))
+}
+
+/// Increment level for commits directly to the main branch, where `None` leaves the version as is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DefaultIncrement(pub Option<IncrementLevel>);
+
+impl Serialize for DefaultIncrement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_increment(&self.0, serializer)
+    }
+}
+
+impl Default for DefaultIncrement {
+    fn default() -> Self {
+        Self(Some(IncrementLevel::Patch))
+    }
+}
+
+fn default_increment_parser() -> impl TypedValueParser<Value = DefaultIncrement> {
+    PossibleValuesParser::new(
+        INCREMENT_LEVEL_NAMES
+            .map(|(_, names)| PossibleValue::new(names[0]).aliases(names[1..].iter().copied()))
+            .into_iter()
+            .chain([PossibleValue::new("none")]),
+    )
+    .map(|name| DefaultIncrement(parse_increment_level(&name)))
+}
+
+/// The increment level for merges of branches whose name starts with a prefix, eg. "minor" for "feature/thing".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchPrefix {
+    pub prefix: String,
+    pub increment: IncrementLevel,
+}
+
+impl Serialize for BranchPrefix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{}={}", self.prefix, self.increment))
+    }
+}
+
+fn parse_branch_prefix(value: &str) -> Result<BranchPrefix, String> {
+    value
+        .split_once('=')
+        .filter(|(prefix, _)| !prefix.is_empty())
+        .and_then(|(prefix, level)| {
+            Some(BranchPrefix {
+                prefix: prefix.to_string(),
+                increment: parse_increment_level(level)?,
+            })
+        })
+        .ok_or_else(|| {
+            "expected PREFIX=LEVEL, with LEVEL an increment level, eg. \"feature=minor\""
+                .to_string()
+        })
+}
+
+/// What to do when the computed version exceeds the ceiling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnCeiling {
+    #[default]
+    Error,
+    Clamp,
+}
+
+/// What to do when the HEAD commit on the main branch has an empty summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnEmptySummary {
+    #[default]
+    Default,
+    Skip,
+    Error,
+}
+
+/// Which commits decide the increment of a release.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IncrementPolicy {
+    #[default]
+    Head,
+    Highest,
+}
+
+/// Which time of a commit to take its timestamp from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeSource {
+    Author,
+    #[default]
+    Committer,
+}
+
+/// Which tags may serve as the base version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Base {
+    #[default]
+    Any,
+    Stable,
+}
+
+impl Base {
+    /// Whether a tagged version may serve as the base version.
+    pub fn admits(&self, version: &Version) -> bool {
+        match self {
+            Base::Any => true,
+            Base::Stable => version.pre.is_empty(),
+        }
+    }
+}
+
+#[non_exhaustive]
+pub enum Error {
+    HeadWithSemverTag,
+    CommitSummaryWithoutIncrementLevel,
+    EmptyPrereleaseIdentifier(String),
+    InvalidPrereleaseIdentifier(String),
+    InvalidPrereleaseRevision(String),
+    LeadingZeroPrerelease(String),
+    InvalidPrerelease(String),
+    InvalidBuildMetadata(String),
+    InvalidIncrementLabels(String),
+    InvalidBumpFile(String),
+    VersionExceedsCeiling {
+        version: Version,
+        ceiling: Version,
+    },
+    VersionOverflow {
+        version: Version,
+        level: IncrementLevel,
+    },
+    NotFirstParentAncestor {
+        from: String,
+        to: String,
+    },
+    MatchExpressionWithoutGroup(String),
+    InvalidTagTemplate(String),
+    DirtyWorkingTree(Vec<String>),
+    UnbornBranch(String),
+    DetachedHeadNotCommit(Oid),
+    CorruptHead(git2::Error),
+    InvalidMainCommit(String),
+    TagVerification(io::Error),
+    RepositoryNotFound(git2::Error),
+    MissingSignature(git2::Error),
+    Git(git2::Error),
+    Regex(regex::Error),
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Error(\"")?;
+        Display::fmt(self, f)?;
+        f.write_str("\")")?;
+        Ok(())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&user_message(self))
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::RepositoryNotFound(error)
+            | Error::MissingSignature(error)
+            | Error::CorruptHead(error)
+            | Error::Git(error) => Some(error),
+            Error::Regex(error) => Some(error),
+            Error::TagVerification(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// The process exit code reported by the CLI for this error. These are stable, so scripts may rely on them.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Regex(_)
+            | Error::InvalidIncrementLabels(_)
+            | Error::InvalidBumpFile(_)
+            | Error::NotFirstParentAncestor { .. }
+            | Error::MatchExpressionWithoutGroup(_)
+            | Error::InvalidTagTemplate(_)
+            | Error::InvalidMainCommit(_) => 2,
+            Error::RepositoryNotFound(_) => 3,
+            Error::HeadWithSemverTag => 4,
+            Error::CommitSummaryWithoutIncrementLevel => 5,
+            Error::EmptyPrereleaseIdentifier(_)
+            | Error::InvalidPrereleaseIdentifier(_)
+            | Error::InvalidPrereleaseRevision(_)
+            | Error::LeadingZeroPrerelease(_)
+            | Error::InvalidPrerelease(_)
+            | Error::InvalidBuildMetadata(_) => 6,
+            Error::VersionExceedsCeiling { .. } | Error::VersionOverflow { .. } => 9,
+            Error::DirtyWorkingTree(_) => 12,
+            Error::UnbornBranch(_) => 13,
+            Error::DetachedHeadNotCommit(_)
+            | Error::CorruptHead(_)
+            | Error::TagVerification(_)
+            | Error::MissingSignature(_)
+            | Error::Git(_) => 1,
+        }
+    }
+
+    /// The message for this error with its arguments left as "{name}" placeholders, eg. to look up a translation.
+    pub fn message_template(&self) -> &'static str {
+        match self {
+            Error::HeadWithSemverTag => "HEAD already tagged with semver",
+            Error::CommitSummaryWithoutIncrementLevel => {
+                "cannot derive version increment level from commit summary"
+            }
+            Error::EmptyPrereleaseIdentifier(_) => {
+                "prerelease identifier \"{id}\" is empty once slugged"
+            }
+            Error::InvalidPrereleaseIdentifier(_) => {
+                "prerelease identifier \"{id}\" is not a valid semver prerelease"
+            }
+            Error::InvalidPrereleaseRevision(_) => {
+                "prerelease revision \"{revision}\" is not a valid semver prerelease"
+            }
+            Error::LeadingZeroPrerelease(_) => {
+                "prerelease \"{pre}\" has a numeric identifier with a leading zero, which semver forbids"
+            }
+            Error::InvalidPrerelease(_) => "prerelease \"{pre}\" is not a valid semver prerelease",
+            Error::InvalidBuildMetadata(_) => {
+                "build metadata \"{build}\" is not valid semver build metadata"
+            }
+            Error::InvalidIncrementLabels(_) => {
+                "labels in environment variable {var} are neither a JSON array of strings nor a comma separated list"
+            }
+            Error::InvalidBumpFile(_) => {
+                "bump file {path} holds neither an increment level nor \"none\""
+            }
+            Error::VersionExceedsCeiling { .. } => {
+                "version {version} exceeds the maximum version {ceiling}"
+            }
+            Error::VersionOverflow { .. } => {
+                "version {version} cannot be incremented by {level}, its {level} component is at the maximum"
+            }
+            Error::MatchExpressionWithoutGroup(_) => {
+                "match expression \"{expression}\" has no capture group to read the increment level from"
+            }
+            Error::NotFirstParentAncestor { .. } => "{from} is not a first-parent ancestor of {to}",
+            Error::InvalidTagTemplate(_) => {
+                "tag template \"{template}\" must have the version placeholder exactly once, and may only have the component placeholder along with a component"
+            }
+            Error::DirtyWorkingTree(_) => {
+                "working tree has uncommitted changes to {paths}, commit or stash them before tagging"
+            }
+            Error::UnbornBranch(_) => {
+                "HEAD is on branch {branch}, which has no commits yet, commit something to compute a version"
+            }
+            Error::DetachedHeadNotCommit(_) => {
+                "HEAD is detached at {oid}, which is not a commit in this repository"
+            }
+            Error::CorruptHead(_) => "HEAD cannot be read, the repository may be corrupt: {error}",
+            Error::InvalidMainCommit(_) => {
+                "main commit \"{revision}\" does not resolve to a commit in this repository"
+            }
+            Error::TagVerification(_) => "cannot run git to verify tag signatures: {error}",
+            Error::MissingSignature(_) => {
+                "cannot create an annotated tag without a signature, configure user.name and user.email or create a lightweight tag: {error}"
+            }
+            Error::RepositoryNotFound(_) | Error::Git(_) | Error::Regex(_) => "{error}",
+        }
+    }
+
+    /// The named arguments substituted for the placeholders of [`Error::message_template`].
+    pub fn message_arguments(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Error::HeadWithSemverTag | Error::CommitSummaryWithoutIncrementLevel => Vec::new(),
+            Error::EmptyPrereleaseIdentifier(id) | Error::InvalidPrereleaseIdentifier(id) => {
+                vec![("id", id.clone())]
+            }
+            Error::InvalidPrereleaseRevision(revision) => vec![("revision", revision.clone())],
+            Error::LeadingZeroPrerelease(pre) | Error::InvalidPrerelease(pre) => {
+                vec![("pre", pre.clone())]
+            }
+            Error::InvalidBuildMetadata(build) => vec![("build", build.clone())],
+            Error::InvalidIncrementLabels(var) => vec![("var", var.clone())],
+            Error::InvalidBumpFile(path) => vec![("path", path.clone())],
+            Error::VersionExceedsCeiling { version, ceiling } => vec![
+                ("version", version.to_string()),
+                ("ceiling", ceiling.to_string()),
+            ],
+            Error::VersionOverflow { version, level } => vec![
+                ("version", version.to_string()),
+                ("level", level.to_string()),
+            ],
+            Error::MatchExpressionWithoutGroup(expression) => {
+                vec![("expression", expression.clone())]
+            }
+            Error::NotFirstParentAncestor { from, to } => {
+                vec![("from", from.clone()), ("to", to.clone())]
+            }
+            Error::InvalidTagTemplate(template) => vec![("template", template.clone())],
+            Error::DirtyWorkingTree(paths) => vec![("paths", paths.join(", "))],
+            Error::UnbornBranch(branch) => vec![("branch", branch.clone())],
+            Error::DetachedHeadNotCommit(oid) => vec![("oid", oid.to_string())],
+            Error::InvalidMainCommit(revision) => vec![("revision", revision.clone())],
+            Error::RepositoryNotFound(error)
+            | Error::MissingSignature(error)
+            | Error::CorruptHead(error)
+            | Error::Git(error) => vec![("error", error.to_string())],
+            Error::Regex(error) => vec![("error", error.to_string())],
+            Error::TagVerification(error) => vec![("error", error.to_string())],
+        }
+    }
+}
+
+/// The message the CLI prints for an error, so that embedders may present errors consistently. This is the
+/// [`Error::message_template`] with the [`Error::message_arguments`] substituted, and is also the error's `Display`.
+pub fn user_message(error: &Error) -> String {
+    let arguments = error.message_arguments();
+    let mut message = String::new();
+    let mut rest = error.message_template();
+    // Substitute in a single pass, so that an argument which happens to contain a placeholder is left as is.
+    while let Some(open) = rest.find('{') {
+        message.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let argument = rest.find('}').and_then(|close| {
+            arguments
+                .iter()
+                .find(|(name, _)| *name == &rest[1..close])
+                .map(|(_, value)| (close, value))
+        });
+        match argument {
+            Some((close, value)) => {
+                message.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+impl From<git2::Error> for Error {
+    fn from(error: git2::Error) -> Self {
+        Error::Git(error)
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(error: regex::Error) -> Self {
+        Error::Regex(error)
+    }
+}
+
+impl Default for Options {
+    /// The options as they are when no command line arguments are given.
+    fn default() -> Self {
+        #[derive(Parser)]
+        struct Defaults {
+            #[command(flatten)]
+            options: Options,
+        }
+        Defaults::parse_from([env!("CARGO_PKG_NAME")]).options
+    }
+}
+
+/// Open the repository at an explicit path, or else from GIT_DIR and friends, or else by discovery from the current
+/// directory. Searching upwards, from the explicit path as from the current directory, stops at the
+/// GIT_CEILING_DIRECTORIES, eg. so that a nested checkout without a repository of its own does not pick up the parent's.
+pub fn open(path: Option<&Path>) -> Result<Repository, Error> {
+    match path {
+        Some(path) => {
+            let ceiling_dirs = env::var_os("GIT_CEILING_DIRECTORIES")
+                .map(|dirs| env::split_paths(&dirs).collect::<Vec<_>>())
+                .unwrap_or_default();
+            Repository::open_ext(path, RepositoryOpenFlags::empty(), ceiling_dirs)
+        }
+        None => Repository::open_from_env(),
+    }
+    .map_err(|error| match error.code() {
+        ErrorCode::NotFound => Error::RepositoryNotFound(error),
+        _ => Error::Git(error),
+    })
+}
+
+/// The result of computing the version of the repository's HEAD commit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Output {
+    /// The computed version.
+    pub version: Version,
+
+    /// The net increment applied to the base version to reach the computed version.
+    #[serde(serialize_with = "serialize_increment")]
+    pub increment: Option<IncrementLevel>,
+
+    /// The commit tagged with the base version, if any tag was found.
+    #[serde(serialize_with = "serialize_base_commit")]
+    pub base_commit: Option<Oid>,
+
+    /// The name of the tag of the base version as found in the repository, eg. "v1.2.3" or "api@1.2.3" under a
+    /// --tag-template, if any tag was found.
+    pub base_tag: Option<String>,
+
+    /// The commit the version was computed for.
+    #[serde(serialize_with = "serialize_oid")]
+    pub head_commit: Oid,
+}
+
+/// The commit HEAD points at. A HEAD on a branch without commits, eg. in a freshly initialized repository or after the
+/// branch was deleted, a detached HEAD at something other than a commit, and a HEAD which cannot be read at all are
+/// each reported as such rather than as whichever git error resolving them happens to raise.
+pub fn head_commit(repository: &Repository) -> Result<Commit<'_>, Error> {
+    let head = repository
+        .find_reference("HEAD")
+        .map_err(Error::CorruptHead)?;
+    match head.symbolic_target_bytes() {
+        Some(target) => match head.resolve() {
+            Ok(reference) => Ok(reference.peel_to_commit()?),
+            Err(error) if error.code() == ErrorCode::NotFound => {
+                let target = String::from_utf8_lossy(target);
+                Err(Error::UnbornBranch(
+                    target
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(&target)
+                        .to_string(),
+                ))
+            }
+            Err(error) => Err(Error::CorruptHead(error)),
+        },
+        None => {
+            let oid = head
+                .target()
+                .ok_or_else(|| Error::CorruptHead(git2::Error::from_str("HEAD has no target")))?;
+            head.peel_to_commit()
+                .map_err(|_| Error::DetachedHeadNotCommit(oid))
+        }
+    }
+}
+
+/// The commit a revision points at, resolving HEAD as [`head_commit`] does.
+fn revision_commit<'a>(repository: &'a Repository, rev: &str) -> Result<Commit<'a>, Error> {
+    if rev == "HEAD" {
+        head_commit(repository)
+    } else {
+        Ok(repository.revparse_single(rev)?.peel_to_commit()?)
+    }
+}
+
+/// Whether a commit is one of the first-parent ancestors of another, or the other itself.
+fn is_first_parent_ancestor(ancestor: Oid, commit: &Commit) -> bool {
+    std::iter::successors(Some(commit.clone()), |commit| commit.parent(0).ok())
+        .any(|commit| commit.id() == ancestor)
+}
+
+/// Compute the version of the repository's HEAD commit.
+pub fn compute(repository: &Repository, options: &Options) -> Result<Output, Error> {
+    compute_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`compute`] with the tags already read by [`tags`].
+pub fn compute_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Output, Error> {
+    let head_commit = head_commit(repository)?;
+
+    let head_shorthand = prerelease_shorthand(repository, "HEAD", options)?;
+
+    compute_commit(
+        repository,
+        &head_commit,
+        &head_shorthand,
+        tags,
+        options,
+        false,
+    )
+}
+
+/// Compute the version of a revision as if another revision among its first-parent ancestors were the only one tagged.
+/// The version of that base revision is its greatest tag which --base admits, otherwise the version computed for it.
+pub fn compute_between(
+    repository: &Repository,
+    from: &str,
+    to: &str,
+    options: &Options,
+) -> Result<Output, Error> {
+    compute_between_with_tags(repository, from, to, &tags(repository, options)?, options)
+}
+
+/// [`compute_between`] with the tags already read by [`tags`].
+pub fn compute_between_with_tags(
+    repository: &Repository,
+    from: &str,
+    to: &str,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Output, Error> {
+    let from_commit = revision_commit(repository, from)?;
+    let to_commit = revision_commit(repository, to)?;
+
+    if !is_first_parent_ancestor(from_commit.id(), &to_commit) {
+        return Err(Error::NotFirstParentAncestor {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    let from_tag = tags
+        .get(&from_commit.id())
+        .and_then(|tags| tags.iter().rev().find(|t| options.base.admits(&t.version)));
+    let from_version = match from_tag {
+        Some(tag) => tag.version.clone(),
+        None => Version {
+            build: BuildMetadata::EMPTY,
+            ..compute_commit(
+                repository,
+                &from_commit,
+                &prerelease_shorthand(repository, from, options)?,
+                tags,
+                options,
+                false,
+            )?
+            .version
+        },
+    };
+    let base = HashMap::from([(
+        from_commit.id(),
+        vec![Tag {
+            name: from.to_string(),
+            version: from_version,
+        }],
+    )]);
+
+    // An untagged base revision stands in for a tag, but there is no tag to name.
+    Ok(Output {
+        base_tag: from_tag.map(|tag| tag.name.clone()),
+        ..compute_commit(
+            repository,
+            &to_commit,
+            &prerelease_shorthand(repository, to, options)?,
+            &base,
+            options,
+            false,
+        )?
+    })
+}
+
+/// The shorthand name to slug for the prerelease identifier, that of --prerelease-id-ref if given and otherwise of the
+/// reference the revision resolves to, falling back to the revision itself if it names no reference.
+fn prerelease_shorthand(
+    repository: &Repository,
+    rev: &str,
+    options: &Options,
+) -> Result<String, Error> {
+    let reference = match &options.prerelease_id_ref {
+        Some(name) => repository.resolve_reference_from_short_name(name)?,
+        None => match repository
+            .resolve_reference_from_short_name(rev)
+            .and_then(|reference| reference.resolve())
+        {
+            Ok(reference) => reference,
+            Err(_) => return Ok(rev.to_string()),
+        },
+    };
+    Ok(String::from_utf8_lossy(reference.shorthand_bytes()).into_owned())
+}
+
+/// A semver tag reachable from HEAD alongside the version computed for its commit.
+#[derive(Debug)]
+pub struct BulkEntry {
+    /// The name of the tag.
+    pub tag: String,
+
+    /// The commit the tag points at.
+    pub commit: Oid,
+
+    /// The version computed for the commit as though it were HEAD and untagged, ie. the version it would have been
+    /// tagged with.
+    pub next: Result<Output, Error>,
+}
+
+/// Compute the version of every semver tagged commit reachable from HEAD, disregarding the commit's own tags, in order
+/// of ascending tagged version.
+pub fn bulk(repository: &Repository, options: &Options) -> Result<Vec<BulkEntry>, Error> {
+    bulk_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`bulk`] with the tags already read by [`tags`].
+pub fn bulk_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Vec<BulkEntry>, Error> {
+    reachable_tags(repository, "HEAD", tags)?
+        .into_iter()
+        .map(|(oid, tag)| {
+            let commit = repository.find_commit(oid)?;
+            Ok(BulkEntry {
+                tag: tag.name.clone(),
+                commit: oid,
+                next: compute_commit(repository, &commit, &tag.name, tags, options, true),
+            })
+        })
+        .collect()
+}
+
+/// Compute the version of a commit, naming it for any prerelease, optionally disregarding the commit's own tags.
+fn compute_commit(
+    repository: &Repository,
+    head_commit: &Commit,
+    head_shorthand: &str,
+    tags: &TagMap,
+    options: &Options,
+    disregard_own_tags: bool,
+) -> Result<Output, Error> {
+    let head_short_id = String::from_utf8_lossy(&head_commit.as_object().short_id()?).into_owned();
+
+    let commit_match_expression = commit_match_expression(options)?;
+
+    let forced_increment = forced_increment(options)?;
+
+    let head_is_main = is_main(repository, head_commit, options)?;
+
+    let fork_point = if options.relative_to_main && !head_is_main {
+        main_fork_point(repository, head_commit.id(), options)?
+    } else {
+        None
+    };
+
+    let start = match fork_point {
+        Some(fork_point) => Some(repository.find_commit(fork_point)?),
+        None if disregard_own_tags => head_commit.parent(0).ok(),
+        None => Some(head_commit.clone()),
+    };
+    let found = find_base(start, tags, options);
+    // A tagged HEAD has its version already, unless --next takes its tag as the base, but may still be given a prerelease
+    // of it under --prerelease-even-if-tagged, eg. for a candidate artifact built from the release commit.
+    let head_is_tagged =
+        !options.next && found.is_some_and(|(_, commit)| commit == head_commit.id());
+    if head_is_tagged && !options.prerelease_even_if_tagged {
+        return Err(Error::HeadWithSemverTag);
+    }
+    let (base, base_commit, base_tag) = match found {
+        Some((tag, commit)) => (tag.version.clone(), Some(commit), Some(tag.name.clone())),
+        None => (Version::new(0, 0, 0), None, None),
+    };
+    let released = head_is_main && !head_is_tagged;
+
+    let pre = if released {
+        Prerelease::EMPTY
+    } else {
+        prerelease(
+            &options.prerelease_format,
+            options.prerelease_id.as_deref().unwrap_or(head_shorthand),
+            options
+                .prerelease_revision
+                .as_ref()
+                .unwrap_or(&head_short_id),
+            PrereleaseCounts {
+                count: match fork_point {
+                    Some(fork_point) => {
+                        commits_unique_to(repository, head_commit.id(), fork_point)?
+                    }
+                    None => commits_since(head_commit, base_commit),
+                },
+                matched_count: levels_since(
+                    head_commit,
+                    base_commit,
+                    &commit_match_expression,
+                    options,
+                )
+                .len(),
+            },
+            SlugOptions::new(options),
+        )?
+    };
+
+    let build = options_build_metadata(options, head_commit)?;
+
+    let mut tag = base.clone();
+
+    // The increment is decided by the HEAD commit alone, unless --increment-policy highest takes the highest level named
+    // since the base. A tagged HEAD has returned early above unless --next, in which case its own tag is the base it
+    // increments, or --prerelease-even-if-tagged, in which case its own tag is given the prerelease.
+    if released {
+        let increment = if let Some(increment) = forced_increment {
+            Some(increment)
+        } else if let Some(increment) =
+            bump_file_increment(repository, head_commit, base_commit, options)?
+        {
+            increment
+        } else if options.no_implicit_initial_bump && base_commit.is_none() {
+            // Without a version tag, the seed 0.0.0 is left as is under --no-implicit-initial-bump.
+            None
+        } else if let Some(increment) = options
+            .resolver_cmd
+            .as_deref()
+            .and_then(|command| resolved_increment(command, head_commit))
+        {
+            increment
+        } else if let Some(increment) = options
+            .trailer
+            .as_deref()
+            .zip(head_commit.message())
+            .and_then(|(key, message)| trailer_increment_level(message, key))
+        {
+            Some(increment)
+        } else if let Some(increment) = (options.increment_policy == IncrementPolicy::Highest)
+            .then(|| {
+                levels_since(head_commit, base_commit, &commit_match_expression, options)
+                    .into_iter()
+                    .max()
+            })
+            .flatten()
+        {
+            Some(increment)
+        } else if skips_empty_summary(head_commit.summary(), options)? {
+            // The version is left as is for an empty summary under --on-empty-summary skip.
+            None
+        } else if head_commit.parent(1).is_ok() {
+            let increment_level = if let Some(increment_level) =
+                head_commit.summary().and_then(|summary| {
+                    branch_prefix_increment_level(summary, &options.branch_prefix_map)
+                }) {
+                Some(increment_level)
+            } else if options.highest_match {
+                head_commit.message().and_then(|message| {
+                    determine_highest_increment_level(message, &commit_match_expression)
+                })
+            } else {
+                head_commit.summary().and_then(|summary| {
+                    determine_increment_level(summary, &commit_match_expression)
+                })
+            };
+            match increment_level {
+                Some(increment_level) => Some(increment_level),
+                None if options.no_default_head_bump => None,
+                None => return Err(Error::CommitSummaryWithoutIncrementLevel),
+            }
+        } else if options.no_default_head_bump {
+            None
+        } else {
+            options.default_increment.0
+        };
+        // None orders before any increment level, so the floor also applies where the version would be left as is.
+        if let Some(increment) = increment.max(options.min_increment) {
+            checked_increment(&mut tag, increment)?;
+        }
+    } else {
+        tag.pre = pre;
+    }
+
+    finish(
+        base,
+        base_commit,
+        base_tag,
+        tag,
+        build,
+        head_commit.id(),
+        options,
+    )
+}
+
+/// Preview the version of a commit of the staged changes on top of HEAD with the given summary, as it would be
+/// released from the main branch. The summary decides the increment as a merge commit's would, falling back on
+/// --default-increment when it matches no increment level, and a tag on HEAD itself is taken as the base.
+pub fn compute_staged(
+    repository: &Repository,
+    summary: &str,
+    options: &Options,
+) -> Result<Output, Error> {
+    compute_staged_with_tags(repository, summary, &tags(repository, options)?, options)
+}
+
+/// [`compute_staged`] with the tags already read by [`tags`].
+pub fn compute_staged_with_tags(
+    repository: &Repository,
+    summary: &str,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Output, Error> {
+    let head_commit = head_commit(repository)?;
+
+    let commit_match_expression = commit_match_expression(options)?;
+
+    let forced_increment = forced_increment(options)?;
+
+    let build = options_build_metadata(options, &head_commit)?;
+
+    let (base, base_commit, base_tag) = match find_base(Some(head_commit.clone()), tags, options) {
+        Some((tag, commit)) => (tag.version.clone(), Some(commit), Some(tag.name.clone())),
+        None => (Version::new(0, 0, 0), None, None),
+    };
+
+    let mut tag = base.clone();
+
+    let increment = match forced_increment {
+        Some(increment) => Some(increment),
+        None if options.no_implicit_initial_bump && base_commit.is_none() => None,
+        None if skips_empty_summary(Some(summary), options)? => None,
+        None => branch_prefix_increment_level(summary, &options.branch_prefix_map)
+            .or_else(|| determine_increment_level(summary, &commit_match_expression))
+            .or(
+                match (options.no_default_head_bump, options.default_increment) {
+                    (false, DefaultIncrement(increment)) => increment,
+                    (true, _) => None,
+                },
+            ),
+    };
+    if let Some(increment) = increment.max(options.min_increment) {
+        checked_increment(&mut tag, increment)?;
+    }
+
+    finish(
+        base,
+        base_commit,
+        base_tag,
+        tag,
+        build,
+        head_commit.id(),
+        options,
+    )
+}
+
+/// The increment level declared by the --bump-file in a commit's tree, the inner None leaving the version as is. None if
+/// the file is missing, empty, or the same as in the base commit's tree, the release of which it was already declared for.
+fn bump_file_increment(
+    repository: &Repository,
+    commit: &Commit,
+    base_commit: Option<Oid>,
+    options: &Options,
+) -> Result<Option<Option<IncrementLevel>>, Error> {
+    let Some(path) = &options.bump_file else {
+        return Ok(None);
+    };
+    let file = |commit: &Commit| match commit.tree()?.get_path(Path::new(path)) {
+        Ok(entry) => Ok(Some(entry.id())),
+        Err(error) if error.code() == ErrorCode::NotFound => Ok(None),
+        Err(error) => Err(Error::Git(error)),
+    };
+    let Some(blob) = file(commit)? else {
+        return Ok(None);
+    };
+    if let Some(base_commit) = base_commit {
+        if file(&repository.find_commit(base_commit)?)? == Some(blob) {
+            return Ok(None);
+        }
+    }
+    let blob = repository.find_blob(blob)?;
+    match String::from_utf8_lossy(blob.content()).trim() {
+        "" => Ok(None),
+        name if name.eq_ignore_ascii_case("none") => Ok(Some(None)),
+        name => parse_increment_level(name)
+            .map(|level| Some(Some(level)))
+            .ok_or_else(|| Error::InvalidBumpFile(path.clone())),
+    }
+}
+
+/// The increment level the --resolver-cmd prints for a commit, the inner None leaving the version as is. None if the
+/// command cannot be run, fails or prints no increment level, for the usual rules to apply instead.
+fn resolved_increment(command: &str, commit: &Commit) -> Option<Option<IncrementLevel>> {
+    let mut resolver = if cfg!(windows) {
+        let mut resolver = process::Command::new("cmd");
+        resolver.args(["/C", command]);
+        resolver
+    } else {
+        // The first argument after the command is $0, the name of the script.
+        let mut resolver = process::Command::new("sh");
+        resolver.args(["-c", command, "sh"]);
+        resolver
+    };
+    let output = resolver
+        .arg(commit.id().to_string())
+        .arg(commit.summary().unwrap_or_default())
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        name if name.eq_ignore_ascii_case("none") => Some(None),
+        name => parse_increment_level(name).map(Some),
+    }
+}
+
+/// Whether to leave the version as is for a commit with an empty summary, or fail, as --on-empty-summary says. A summary
+/// which is not empty, or an empty one under the default behaviour, is left to be matched as usual.
+fn skips_empty_summary(summary: Option<&str>, options: &Options) -> Result<bool, Error> {
+    if summary.is_some_and(|summary| !summary.trim().is_empty()) {
+        return Ok(false);
+    }
+    match options.on_empty_summary {
+        OnEmptySummary::Default => Ok(false),
+        OnEmptySummary::Skip => Ok(true),
+        OnEmptySummary::Error => Err(Error::CommitSummaryWithoutIncrementLevel),
+    }
+}
+
+/// A dump of the walk for the base version of HEAD, for reproducing a computation from a bug report.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WalkTrace {
+    /// The main branch chosen among the --main-branch candidates, if any exists and no --main-commit stands in for it.
+    pub main_branch: Option<String>,
+
+    /// Whether HEAD is released from the main branch rather than as a prerelease.
+    pub head_is_main: bool,
+
+    /// The commits visited, from HEAD along first parents down to the base commit, or the root if there is none, leaving
+    /// out those by authors --only-author does not admit.
+    pub commits: Vec<WalkStep>,
+}
+
+/// A commit visited by the walk for the base version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WalkStep {
+    /// The visited commit.
+    #[serde(serialize_with = "serialize_oid")]
+    pub commit: Oid,
+
+    /// The parents of the commit, the first parent being the next visited.
+    #[serde(serialize_with = "serialize_oids")]
+    pub parents: Vec<Oid>,
+
+    /// The summary of the commit, if it has one which is valid UTF-8.
+    pub summary: Option<String>,
+
+    /// The increment level the summary names under --branch-prefix-map or --match-expression, if any.
+    #[serde(serialize_with = "serialize_forced_increment")]
+    pub level: Option<IncrementLevel>,
+
+    /// The semver tags of the commit following the --tag-template, in ascending order of version.
+    pub tags: Vec<String>,
+
+    /// Whether one of the tags is admitted by --base, making the commit the base commit which ends the walk.
+    pub base: bool,
+}
+
+/// Trace the walk for the base version of HEAD, visiting the same commits as [`compute`] does.
+pub fn trace_walk(repository: &Repository, options: &Options) -> Result<WalkTrace, Error> {
+    trace_walk_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`trace_walk`] with the tags already read by [`tags`].
+pub fn trace_walk_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<WalkTrace, Error> {
+    let head_commit = head_commit(repository)?;
+    let commit_match_expression = commit_match_expression(options)?;
+
+    let mut commits = Vec::new();
+    let mut next = Some(head_commit.clone());
+    while let Some(commit) = next {
+        let summary = commit.summary().map(str::to_string);
+        let commit_tags = tags
+            .get(&commit.id())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let base = commit_tags
+            .iter()
+            .any(|tag| options.base.admits(&tag.version));
+        if !base && !admits_author(&commit, options) {
+            next = commit.parent(0).ok();
+            continue;
+        }
+        commits.push(WalkStep {
+            commit: commit.id(),
+            parents: commit.parent_ids().collect(),
+            level: summary.as_deref().and_then(|summary| {
+                summary_increment_level(summary, &commit_match_expression, options)
+            }),
+            summary,
+            tags: commit_tags.iter().map(|tag| tag.name.clone()).collect(),
+            base,
+        });
+        if base {
+            break;
+        }
+        next = commit.parent(0).ok();
+    }
+
+    Ok(WalkTrace {
+        main_branch: match options.main_commit {
+            Some(_) => None,
+            None => existing_main_branches(repository, options)?
+                .first()
+                .map(|branch| branch.to_string()),
+        },
+        head_is_main: is_main(repository, &head_commit, options)?,
+        commits,
+    })
+}
+
+/// The commits since the base version naming an increment level, or none, for release notes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ChangelogSection {
+    /// The increment level the summaries of the commits name.
+    #[serde(serialize_with = "serialize_increment")]
+    pub level: Option<IncrementLevel>,
+
+    /// The commits, from the most recent.
+    pub commits: Vec<ChangelogEntry>,
+}
+
+/// A commit listed in a [`ChangelogSection`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ChangelogEntry {
+    /// The commit.
+    #[serde(serialize_with = "serialize_oid")]
+    pub commit: Oid,
+
+    /// The summary of the commit, if it has one which is valid UTF-8.
+    pub summary: Option<String>,
+}
+
+/// Group the first-parent commits since the base version of HEAD by the increment level their summary names, as
+/// [`trace_walk`] matches them, from major down to the commits naming none. Levels no commit names are left out.
+pub fn changelog(
+    repository: &Repository,
+    options: &Options,
+) -> Result<Vec<ChangelogSection>, Error> {
+    changelog_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`changelog`] with the tags already read by [`tags`].
+pub fn changelog_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Vec<ChangelogSection>, Error> {
+    let entries = match &options.changelog_since {
+        Some(since) => changelog_since(repository, since, options)?,
+        None => trace_walk_with_tags(repository, tags, options)?
+            .commits
+            .into_iter()
+            .filter(|step| !step.base)
+            .map(|step| {
+                (
+                    step.level,
+                    ChangelogEntry {
+                        commit: step.commit,
+                        summary: step.summary,
+                    },
+                )
+            })
+            .collect(),
+    };
+    let levels = [
+        Some(IncrementLevel::Major),
+        Some(IncrementLevel::Minor),
+        Some(IncrementLevel::Patch),
+        None,
+    ];
+    Ok(levels
+        .into_iter()
+        .map(|level| ChangelogSection {
+            level,
+            commits: entries
+                .iter()
+                .filter(|(entry_level, _)| *entry_level == level)
+                .map(|(_, entry)| entry.clone())
+                .collect(),
+        })
+        .filter(|section| !section.commits.is_empty())
+        .collect())
+}
+
+/// The first-parent commits from HEAD down to, but excluding, the --changelog-since revision, with the increment level
+/// each summary names.
+fn changelog_since(
+    repository: &Repository,
+    since: &str,
+    options: &Options,
+) -> Result<Vec<(Option<IncrementLevel>, ChangelogEntry)>, Error> {
+    let head_commit = head_commit(repository)?;
+    let since_commit = revision_commit(repository, since)?;
+    if !is_first_parent_ancestor(since_commit.id(), &head_commit) {
+        return Err(Error::NotFirstParentAncestor {
+            from: since.to_string(),
+            to: "HEAD".to_string(),
+        });
+    }
+    let commit_match_expression = commit_match_expression(options)?;
+    Ok(
+        std::iter::successors(Some(head_commit), |commit| commit.parent(0).ok())
+            .take_while(|commit| commit.id() != since_commit.id())
+            .filter(|commit| admits_author(commit, options))
+            .map(|commit| {
+                let summary = commit.summary().map(str::to_string);
+                (
+                    summary.as_deref().and_then(|summary| {
+                        summary_increment_level(summary, &commit_match_expression, options)
+                    }),
+                    ChangelogEntry {
+                        commit: commit.id(),
+                        summary,
+                    },
+                )
+            })
+            .collect(),
+    )
+}
+
+/// How many of the first-parent commits since the base version name each increment level, as [`changelog`] groups them,
+/// eg. to sanity-check the composition of a release.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LevelCounts {
+    pub major: usize,
+    pub minor: usize,
+    pub patch: usize,
+
+    /// The commits naming no increment level.
+    pub skipped: usize,
+}
+
+/// Count the first-parent commits since the base version of HEAD by the increment level their summary names.
+pub fn level_counts(repository: &Repository, options: &Options) -> Result<LevelCounts, Error> {
+    level_counts_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`level_counts`] with the tags already read by [`tags`].
+pub fn level_counts_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<LevelCounts, Error> {
+    let mut counts = LevelCounts::default();
+    for step in trace_walk_with_tags(repository, tags, options)?.commits {
+        if step.base {
+            continue;
+        }
+        match step.level {
+            Some(IncrementLevel::Major) => counts.major += 1,
+            Some(IncrementLevel::Minor) => counts.minor += 1,
+            Some(IncrementLevel::Patch) => counts.patch += 1,
+            None => counts.skipped += 1,
+        }
+    }
+    Ok(counts)
+}
+
+/// The increment level a commit summary names under --branch-prefix-map or --match-expression, if any.
+fn summary_increment_level(
+    summary: &str,
+    commit_match_expression: &Regex,
+    options: &Options,
+) -> Option<IncrementLevel> {
+    branch_prefix_increment_level(summary, &options.branch_prefix_map)
+        .or_else(|| determine_increment_level(summary, commit_match_expression))
+}
+
+/// The --match-expression, which must have a capture group for the increment level.
+fn commit_match_expression(options: &Options) -> Result<Regex, Error> {
+    let commit_match_expression = Regex::new(options.match_expression.as_str())?;
+    // The implicit group 0 is the whole match, which cannot be an increment level.
+    if commit_match_expression.captures_len() < 2 {
+        return Err(Error::MatchExpressionWithoutGroup(
+            options.match_expression.clone(),
+        ));
+    }
+    Ok(commit_match_expression)
+}
+
+/// The increment given by --increment, otherwise by the labels in the --increment-from-env variable, otherwise by the
+/// pull request title in the --bump-from-title-env variable.
+fn forced_increment(options: &Options) -> Result<Option<IncrementLevel>, Error> {
+    let labelled = match (options.increment, &options.increment_from_env) {
+        (Some(increment), _) => Some(increment),
+        (None, Some(var)) => env::var(var)
+            .ok()
+            .map(|labels| {
+                increment_from_labels(&labels)
+                    .ok_or_else(|| Error::InvalidIncrementLabels(var.clone()))
+            })
+            .transpose()?
+            .flatten(),
+        (None, None) => None,
+    };
+    Ok(match (labelled, &options.bump_from_title_env) {
+        (Some(increment), _) => Some(increment),
+        (None, Some(var)) => match env::var(var) {
+            Ok(title) => determine_increment_level(&title, &commit_match_expression(options)?),
+            Err(_) => None,
+        },
+        (None, None) => None,
+    })
+}
+
+/// The build metadata given by --build-metadata and --build-from-env.
+fn options_build_metadata(options: &Options, commit: &Commit) -> Result<BuildMetadata, Error> {
+    build_metadata(
+        options
+            .build_metadata
+            .as_ref()
+            .map(|build| {
+                let time = match options.time_source {
+                    TimeSource::Author => commit.author().when(),
+                    TimeSource::Committer => commit.time(),
+                };
+                build.replace("{build_timestamp}", &utc_timestamp(time.seconds()))
+            })
+            .as_deref(),
+        options
+            .build_from_env
+            .as_ref()
+            .and_then(|var| env::var(var).ok())
+            .as_deref(),
+    )
+}
+
+/// Find the nearest commit with a tag which --base admits among the first-parent ancestors of a commit, inclusive, and
+/// the greatest such version tagged on it.
+fn find_base<'a>(
+    start: Option<Commit>,
+    tags: &'a TagMap,
+    options: &Options,
+) -> Option<(&'a Tag, Oid)> {
+    // Only first-parent ancestors are visited, so tags elsewhere in the repository are never eligible as the base.
+    let mut commits = VecDeque::from_iter(start);
+
+    while let Some(commit) = commits.pop_front() {
+        // Of several tags on the same commit, the greatest version which --base admits is the base.
+        if let Some(t) = tags
+            .get(&commit.id())
+            .and_then(|tags| tags.iter().rev().find(|t| options.base.admits(&t.version)))
+        {
+            return Some((t, commit.id()));
+        }
+        if let Ok(parent_id) = commit.parent(0) {
+            commits.push_back(parent_id);
+        }
+    }
+    None
+}
+
+/// Increment a version, failing rather than overflowing if the incremented component is already at its maximum.
+fn checked_increment(version: &mut Version, level: IncrementLevel) -> Result<(), Error> {
+    let component = match level {
+        IncrementLevel::Patch => version.patch,
+        IncrementLevel::Minor => version.minor,
+        IncrementLevel::Major => version.major,
+    };
+    if component == u64::MAX {
+        return Err(Error::VersionOverflow {
+            version: version.clone(),
+            level,
+        });
+    }
+    // The lesser components are reset anyway, but incrementing bumps them on the way, which could overflow too.
+    if level > IncrementLevel::Patch {
+        version.patch = 0;
+    }
+    if level > IncrementLevel::Minor {
+        version.minor = 0;
+    }
+    version.increment(level);
+    Ok(())
+}
+
+/// Cap the incremented version at --max-version and attach the build metadata.
+fn finish(
+    base: Version,
+    base_commit: Option<Oid>,
+    base_tag: Option<String>,
+    mut tag: Version,
+    build: BuildMetadata,
+    head_commit: Oid,
+    options: &Options,
+) -> Result<Output, Error> {
+    if let Some(ceiling) = &options.max_version {
+        if tag.cmp_precedence(ceiling).is_gt() {
+            match options.on_ceiling {
+                OnCeiling::Error => {
+                    return Err(Error::VersionExceedsCeiling {
+                        version: tag,
+                        ceiling: ceiling.clone(),
+                    })
+                }
+                OnCeiling::Clamp => {
+                    tag = Version {
+                        build: BuildMetadata::EMPTY,
+                        ..ceiling.clone()
+                    }
+                }
+            }
+        }
+    }
+
+    tag.build = build;
+
+    Ok(Output {
+        increment: net_increment(&base, &tag),
+        version: tag,
+        base_commit,
+        base_tag,
+        head_commit,
+    })
+}
+
+/// List the semver tagged commits reachable from a revision, sorted by ascending version.
+///
+/// ```
+/// # use git2::{Repository, Signature};
+/// # use git_semversion::{reachable_versions, Options};
+/// # let dir = tempfile::TempDir::new().unwrap();
+/// # let repository = Repository::init(dir.path()).unwrap();
+/// # let signature = Signature::now("Test", "test@example.com").unwrap();
+/// # let tree = repository.find_tree(repository.index().unwrap().write_tree().unwrap()).unwrap();
+/// # let first = repository.commit(Some("HEAD"), &signature, &signature, "First", &tree, &[]).unwrap();
+/// # let first_commit = repository.find_commit(first).unwrap();
+/// # let second = repository.commit(Some("HEAD"), &signature, &signature, "Second", &tree, &[&first_commit]).unwrap();
+/// repository.tag_lightweight("1.0.0", &repository.find_object(first, None)?, false)?;
+/// repository.tag_lightweight("1.1.0", &repository.find_object(second, None)?, false)?;
+///
+/// let versions = reachable_versions(&repository, "HEAD~", &Options::default())?;
+///
+/// assert_eq!(versions, vec![(first, "1.0.0".parse()?)]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn reachable_versions(
+    repository: &Repository,
+    rev: &str,
+    options: &Options,
+) -> Result<Vec<(Oid, Version)>, Error> {
+    reachable_versions_with_tags(repository, rev, &tags(repository, options)?)
+}
+
+/// [`reachable_versions`] with the tags already read by [`tags`].
+pub fn reachable_versions_with_tags(
+    repository: &Repository,
+    rev: &str,
+    tags: &TagMap,
+) -> Result<Vec<(Oid, Version)>, Error> {
+    Ok(reachable_tags(repository, rev, tags)?
+        .into_iter()
+        .map(|(oid, tag)| (oid, tag.version.clone()))
+        .collect())
+}
+
+/// List the tagged commits reachable from a revision, sorted by ascending version and then name. A single walk visits each reachable
+/// commit once, looking it up among the tags, rather than checking the reachability of each tag in turn.
+fn reachable_tags<'a>(
+    repository: &Repository,
+    rev: &str,
+    tags: &'a TagMap,
+) -> Result<Vec<(Oid, &'a Tag)>, Error> {
+    let mut revwalk = repository.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL)?;
+    revwalk.push(revision_commit(repository, rev)?.id())?;
+    let mut reachable = Vec::new();
+    for oid in revwalk {
+        let oid = oid?;
+        reachable.extend(tags.get(&oid).into_iter().flatten().map(|tag| (oid, tag)));
+    }
+    // Equal versions, tagged with differing build metadata, are ordered by name rather than by the order of the walk.
+    reachable.sort_by(|(_, a), (_, b)| a.version.cmp(&b.version).then_with(|| a.name.cmp(&b.name)));
+    Ok(reachable)
+}
+
+/// The most significant version component which differs between two versions, ignoring prerelease and build metadata.
+pub fn net_increment(from: &Version, to: &Version) -> Option<IncrementLevel> {
+    if from.major != to.major {
+        Some(IncrementLevel::Major)
+    } else if from.minor != to.minor {
+        Some(IncrementLevel::Minor)
+    } else if from.patch != to.patch {
+        Some(IncrementLevel::Patch)
+    } else {
+        None
+    }
+}
+
+/// Name an optional increment level, spelling out the absence of one as "none".
+pub fn increment_name(increment: Option<IncrementLevel>) -> String {
+    increment.map_or_else(|| "none".to_string(), |level| level.to_string())
+}
+
+fn serialize_increment<S: Serializer>(
+    increment: &Option<IncrementLevel>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&increment_name(*increment))
+}
+
+fn serialize_forced_increment<S: Serializer>(
+    increment: &Option<IncrementLevel>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match increment {
+        Some(level) => serializer.collect_str(level),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_base_commit<S: Serializer>(
+    base_commit: &Option<Oid>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match base_commit {
+        Some(oid) => serializer.collect_str(oid),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_oid<S: Serializer>(oid: &Oid, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(oid)
+}
+
+fn serialize_oids<S: Serializer>(oids: &[Oid], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(oids.iter().map(Oid::to_string))
+}
+
+/// Whether a commit is released from the main branch, being on it or, under --treat-ahead-of-main-as-main, ahead of it.
+fn is_main(repository: &Repository, commit: &Commit, options: &Options) -> Result<bool, Error> {
+    Ok(match main_tip(repository, options)? {
+        Some(tip) => {
+            contains(repository, tip, commit.id())?
+                || options.treat_ahead_of_main_as_main && is_ahead_of(commit, tip)
+        }
+        None => false,
+    })
+}
+
+/// The tip of the main branch, being the --main-commit if given and otherwise the tip of the first existing --main-branch
+/// candidate, None if there is none.
+fn main_tip(repository: &Repository, options: &Options) -> Result<Option<Oid>, Error> {
+    if let Some(revision) = &options.main_commit {
+        return match repository
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+        {
+            Ok(commit) => Ok(Some(commit.id())),
+            Err(error)
+                if matches!(
+                    error.code(),
+                    ErrorCode::NotFound
+                        | ErrorCode::InvalidSpec
+                        | ErrorCode::Ambiguous
+                        | ErrorCode::Peel
+                ) =>
+            {
+                Err(Error::InvalidMainCommit(revision.clone()))
+            }
+            Err(error) => Err(error.into()),
+        };
+    }
+    match existing_main_branches(repository, options)?.first() {
+        Some(main_branch) => branch_tip(repository, main_branch),
+        None => Ok(None),
+    }
+}
+
+/// The --main-branch candidates which exist as local branches, or as remote-tracking branches of origin, in the order
+/// given.
+pub fn existing_main_branches<'a>(
+    repository: &Repository,
+    options: &'a Options,
+) -> Result<Vec<&'a str>, Error> {
+    let mut existing = Vec::new();
+    for branch in &options.main_branch {
+        if branch_tip(repository, branch)?.is_some() {
+            existing.push(branch.as_str());
+        }
+    }
+    Ok(existing)
+}
+
+/// The tip of a local branch, falling back on the remote-tracking branch of origin, eg. "origin/main", as CI clones often
+/// have no local branch but the one checked out, if any. None if neither exists.
+fn branch_tip(repository: &Repository, branch: &str) -> Result<Option<Oid>, Error> {
+    for (name, branch_type) in [
+        (branch.to_string(), BranchType::Local),
+        (format!("{DEFAULT_REMOTE}/{branch}"), BranchType::Remote),
+    ] {
+        match repository.find_branch(&name, branch_type) {
+            Ok(branch) => return Ok(Some(branch.get().peel_to_commit()?.id())),
+            Err(error) if error.code() == ErrorCode::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// The merge base of a commit and the tip of the main branch, as found by [`main_tip`], None if there is no main branch
+/// or they share no history.
+fn main_fork_point(
+    repository: &Repository,
+    commit: Oid,
+    options: &Options,
+) -> Result<Option<Oid>, Error> {
+    let Some(tip) = main_tip(repository, options)? else {
+        return Ok(None);
+    };
+    match repository.merge_base(commit, tip) {
+        Ok(merge_base) => Ok(Some(merge_base)),
+        Err(error) if error.code() == ErrorCode::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Whether a commit is a tip or one of its ancestors.
+fn contains(repository: &Repository, tip: Oid, commit: Oid) -> Result<bool, Error> {
+    Ok(tip == commit || repository.graph_descendant_of(tip, commit)?)
+}
+
+/// Whether a tip is a commit or one of its first-parent ancestors.
+fn is_ahead_of(commit: &Commit, tip: Oid) -> bool {
+    std::iter::successors(Some(commit.clone()), |commit| commit.parent(0).ok())
+        .any(|commit| commit.id() == tip)
+}
+
+/// The version for each increment level in ascending order, as the base version found for HEAD would be incremented, eg.
+/// to show the versions a release could be made as. Build metadata and --max-version take no part.
+pub fn preview_increments(
+    repository: &Repository,
+    options: &Options,
+) -> Result<Vec<(IncrementLevel, Version)>, Error> {
+    preview_increments_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`preview_increments`] with the tags already read by [`tags`].
+pub fn preview_increments_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<Vec<(IncrementLevel, Version)>, Error> {
+    let head_commit = head_commit(repository)?;
+    let base = find_base(Some(head_commit), tags, options)
+        .map_or_else(|| Version::new(0, 0, 0), |(tag, _)| tag.version.clone());
+    INCREMENT_LEVEL_NAMES
+        .into_iter()
+        .map(|(level, _)| {
+            let mut version = base.clone();
+            checked_increment(&mut version, level)?;
+            Ok((level, version))
+        })
+        .collect()
+}
+
+/// The greatest version without a prerelease tagged on a commit reachable from HEAD, ie. the latest release.
+pub fn latest_stable_version(
+    repository: &Repository,
+    options: &Options,
+) -> Result<Option<Version>, Error> {
+    latest_stable_version_with_tags(repository, &tags(repository, options)?)
+}
+
+/// [`latest_stable_version`] with the tags already read by [`tags`].
+pub fn latest_stable_version_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+) -> Result<Option<Version>, Error> {
+    Ok(reachable_versions_with_tags(repository, "HEAD", tags)?
+        .into_iter()
+        .rev()
+        .map(|(_, version)| version)
+        .find(|version| version.pre.is_empty()))
+}
+
+/// A commit other than the given one which is tagged with the version, build metadata aside, eg. by a parallel branch,
+/// the least such commit id being chosen if several are.
+pub fn tagged_elsewhere(
+    repository: &Repository,
+    version: &Version,
+    commit: Oid,
+    options: &Options,
+) -> Result<Option<Oid>, Error> {
+    Ok(tagged_elsewhere_with_tags(
+        version,
+        commit,
+        &tags(repository, options)?,
+    ))
+}
+
+/// [`tagged_elsewhere`] with the tags already read by [`tags`].
+pub fn tagged_elsewhere_with_tags(version: &Version, commit: Oid, tags: &TagMap) -> Option<Oid> {
+    let version = Version {
+        build: BuildMetadata::EMPTY,
+        ..version.clone()
+    };
+    tags.iter()
+        .filter(|(oid, tags)| **oid != commit && tags.iter().any(|tag| tag.version == version))
+        .map(|(oid, _)| *oid)
+        .min()
+}
+
+/// A commit other than the base commit which is tagged with the release a computed prerelease is a prerelease of, eg.
+/// "1.2.0" for "1.2.0-feature.1", the least such commit id being chosen if several are. The prerelease orders below that
+/// release, so the branch is likely behind the release line. The base version itself being that release, as for any
+/// branch off a release, is left alone.
+pub fn shadowed_release(
+    repository: &Repository,
+    output: &Output,
+    options: &Options,
+) -> Result<Option<Oid>, Error> {
+    if output.version.pre.is_empty() {
+        return Ok(None);
+    }
+    Ok(shadowed_release_with_tags(
+        output,
+        &tags(repository, options)?,
+    ))
+}
+
+/// [`shadowed_release`] with the tags already read by [`tags`].
+pub fn shadowed_release_with_tags(output: &Output, tags: &TagMap) -> Option<Oid> {
+    if output.version.pre.is_empty() {
+        return None;
+    }
+    let shadows = |version: &Version| {
+        version.pre.is_empty()
+            && (version.major, version.minor, version.patch)
+                == (
+                    output.version.major,
+                    output.version.minor,
+                    output.version.patch,
+                )
+    };
+    tags.iter()
+        .filter(|(oid, tags)| {
+            Some(**oid) != output.base_commit && tags.iter().any(|tag| shadows(&tag.version))
+        })
+        .map(|(oid, _)| *oid)
+        .min()
+}
+
+/// Whether HEAD is tagged with the greatest semver version reachable from it.
+pub fn head_has_latest_version(repository: &Repository, options: &Options) -> Result<bool, Error> {
+    head_has_latest_version_with_tags(repository, &tags(repository, options)?)
+}
+
+/// [`head_has_latest_version`] with the tags already read by [`tags`].
+pub fn head_has_latest_version_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+) -> Result<bool, Error> {
+    let head = head_commit(repository)?.id();
+    let versions = reachable_versions_with_tags(repository, "HEAD", tags)?;
+    Ok(versions.last().is_some_and(|(_, latest)| {
+        versions
+            .iter()
+            .rev()
+            .take_while(|(_, version)| version == latest)
+            .any(|(oid, _)| *oid == head)
+    }))
+}
+
+/// Whether HEAD would be released with a new version, ie. it is not tagged with a version --base admits and an increment
+/// applies to its base version, eg. to gate a publish step. A prerelease, or a version left as is by
+/// --default-increment none, is no release. A tagged HEAD is answered for without computing anything.
+pub fn needs_release(repository: &Repository, options: &Options) -> Result<bool, Error> {
+    needs_release_with_tags(repository, &tags(repository, options)?, options)
+}
+
+/// [`needs_release`] with the tags already read by [`tags`].
+pub fn needs_release_with_tags(
+    repository: &Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<bool, Error> {
+    let head_commit = head_commit(repository)?;
+    if tags
+        .get(&head_commit.id())
+        .is_some_and(|tags| tags.iter().any(|tag| options.base.admits(&tag.version)))
+    {
+        return Ok(false);
+    }
+    let output = compute_commit(
+        repository,
+        &head_commit,
+        &prerelease_shorthand(repository, "HEAD", options)?,
+        tags,
+        options,
+        false,
+    )?;
+    Ok(output.increment.is_some())
+}
+
+/// Tag the HEAD commit with the named tag, eg. as named by [`tag_name`], annotated with the message if given and lightweight otherwise.
+/// Only an annotated tag needs the signature configured by user.name and user.email.
+pub fn create_tag(repository: &Repository, name: &str, message: Option<&str>) -> Result<(), Error> {
+    let head = head_commit(repository)?;
+    match message {
+        Some(message) => {
+            let signature = repository.signature().map_err(Error::MissingSignature)?;
+            repository.tag(name, head.as_object(), &signature, message, false)?;
+        }
+        None => {
+            repository.tag_lightweight(name, head.as_object(), false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fail if the working tree or index differs from HEAD, so that a tag on HEAD captures exactly what is checked out.
+/// Untracked and ignored files are not changes. A bare repository has no working tree to differ.
+pub fn require_clean(repository: &Repository) -> Result<(), Error> {
+    if repository.is_bare() {
+        return Ok(());
+    }
+    let statuses = repository.statuses(Some(
+        StatusOptions::new()
+            .include_untracked(false)
+            .include_ignored(false),
+    ))?;
+    let paths = statuses
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT)
+        .map(|entry| String::from_utf8_lossy(entry.path_bytes()).into_owned())
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DirtyWorkingTree(paths))
+    }
+}
+
+/// Push the named tag to a remote, authenticating with the SSH agent or git's credential helpers.
+pub fn push_tag(repository: &Repository, remote: &str, name: &str) -> Result<(), Error> {
+    let config = repository.config()?;
+    let mut attempted = false;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks
+        .credentials(|url, username, allowed| {
+            // Credentials are asked for again after each rejection, so give up after the first attempt.
+            if attempted {
+                return Err(git2::Error::from_str("authentication failed"));
+            }
+            attempted = true;
+            if allowed.contains(CredentialType::SSH_KEY) {
+                Cred::ssh_key_from_agent(username.unwrap_or("git"))
+            } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                Cred::credential_helper(&config, url, username)
+            } else {
+                Cred::default()
+            }
+        })
+        .push_update_reference(|reference, status| match status {
+            Some(status) => Err(git2::Error::from_str(&format!(
+                "remote rejected {reference}: {status}"
+            ))),
+            None => Ok(()),
+        });
+    let refspec = format!("refs/tags/{name}:refs/tags/{name}");
+    repository.find_remote(remote)?.push(
+        &[refspec],
+        Some(PushOptions::new().remote_callbacks(callbacks)),
+    )?;
+    Ok(())
+}
+
+/// A semver tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    /// The name of the tag, eg. "v1.2.3" under a --tag-template.
+    pub name: String,
+
+    /// The version the name holds, without build metadata.
+    pub version: Version,
+}
+
+/// The semver tags of a repository by the commit they point at, as [`tags`] reads them once for the `_with_tags`
+/// variants of the computations of a single run to share.
+pub type TagMap = HashMap<Oid, Vec<Tag>>;
+
+/// The name of the tag for a version, following the --tag-template.
+pub fn tag_name(version: &Version, options: &Options) -> Result<String, Error> {
+    let (prefix, suffix) = tag_affixes(options)?;
+    Ok(format!("{prefix}{version}{suffix}"))
+}
+
+/// The text before and after the version in the names of version tags, with the component substituted.
+fn tag_affixes(options: &Options) -> Result<(String, String), Error> {
+    let template = &options.tag_template;
+    let invalid = || Error::InvalidTagTemplate(template.clone());
+    let (prefix, suffix) = template
+        .split_once(VERSION_PLACEHOLDER)
+        .ok_or_else(invalid)?;
+    if suffix.contains(VERSION_PLACEHOLDER) {
+        return Err(invalid());
+    }
+    match &options.component {
+        Some(component) => Ok((
+            prefix.replace(COMPONENT_PLACEHOLDER, component),
+            suffix.replace(COMPONENT_PLACEHOLDER, component),
+        )),
+        None if template.contains(COMPONENT_PLACEHOLDER) => Err(invalid()),
+        None => Ok((prefix.to_string(), suffix.to_string())),
+    }
+}
+
+/// Map every commit targeted by semver tags to the tags, sorted by ascending version and then name.
+pub fn tags(repository: &Repository, options: &Options) -> Result<TagMap, Error> {
+    let (prefix, suffix) = tag_affixes(options)?;
+    let mut tags = HashMap::<Oid, Vec<Tag>>::new();
+    for reference in repository.references()?.flatten().filter(Reference::is_tag) {
+        let name = String::from_utf8_lossy(reference.shorthand_bytes());
+        if options
+            .exclude_tag_pattern
+            .iter()
+            .any(|pattern| glob_match(pattern, &name))
+        {
+            continue;
+        }
+        let Some(Ok(mut version)) = name
+            .strip_prefix(prefix.as_str())
+            .and_then(|name| name.strip_suffix(suffix.as_str()))
+            .map(Version::parse)
+        else {
+            continue;
+        };
+        // Build metadata takes no part in version precedence, so it is not carried over from tags.
+        version.build = BuildMetadata::EMPTY;
+        // A lightweight tag targets the tagged object itself, and an annotated tag a tag object targeting it in turn,
+        // possibly through further tag objects, so peeling every tag object away finds the tagged object for both.
+        // A dangling tag, whose target is missing, is passed over like a tag of anything other than a commit.
+        let Ok(target) = reference.peel(ObjectType::Any) else {
+            continue;
+        };
+        // Tags of trees or blobs, however unusual, are no version of any commit.
+        if target.kind() != Some(ObjectType::Commit) {
+            continue;
+        }
+        // Only a tag object can carry a signature, so a lightweight tag is passed over without spawning git for it.
+        if options.require_signed_tags
+            && !(reference.peel_to_tag().is_ok() && verified_tag(repository, &name)?)
+        {
+            continue;
+        }
+        tags.entry(target.id()).or_default().push(Tag {
+            name: name.into_owned(),
+            version,
+        });
+    }
+    for tags in tags.values_mut() {
+        tags.sort_by(|a, b| a.version.cmp(&b.version).then_with(|| a.name.cmp(&b.name)));
+    }
+    Ok(tags)
+}
+
+/// Whether "git verify-tag" accepts the signature of a tag, as libgit2 cannot verify signatures itself.
+fn verified_tag(repository: &Repository, name: &str) -> Result<bool, Error> {
+    let status = process::Command::new("git")
+        .arg("--git-dir")
+        .arg(repository.path())
+        .args(["verify-tag", &format!("refs/tags/{name}")])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(Error::TagVerification)?;
+    Ok(status.success())
+}
+
+/// Match text against a glob pattern, where "*" matches any run of characters and "?" matches any single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last "*" seen in the pattern, and of the text it was tried against.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    backtrack = Some((star, tried + 1));
+                    p = star + 1;
+                    t = tried + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The increment level of the first branch named in a merge commit summary, eg. "Merge branch 'feature/thing'" or
+/// "Merge pull request #1 from me/feature/thing", whose prefix is mapped, the prefix being any path segment but the last.
+fn branch_prefix_increment_level(summary: &str, map: &[BranchPrefix]) -> Option<IncrementLevel> {
+    summary
+        .split(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+        .flat_map(|word| {
+            word.rsplit_once('/')
+                .map_or("", |(prefixes, _)| prefixes)
+                .split('/')
+        })
+        .find_map(|segment| {
+            map.iter()
+                .find(|prefix| prefix.prefix == segment)
+                .map(|prefix| prefix.increment)
+        })
+}
+
+fn determine_increment_level(summary: &str, match_expression: &Regex) -> Option<IncrementLevel> {
+    captured_increment_level(&match_expression.captures(summary)?)
+}
+
+/// Find the greatest increment level among every match of the expression in the text, eg. a squash merge's message
+/// bundling several branches.
+fn determine_highest_increment_level(
+    text: &str,
+    match_expression: &Regex,
+) -> Option<IncrementLevel> {
+    match_expression
+        .captures_iter(text)
+        .filter_map(|captures| captured_increment_level(&captures))
+        .max()
+}
+
+fn captured_increment_level(captures: &Captures) -> Option<IncrementLevel> {
+    let level = captures
+        .name(INCREMENT_LEVEL_CAPTURE_NAME)
+        .or_else(|| captures.get(1))?;
+    parse_increment_level(level.as_str())
+}
+
+/// Read the increment level from the last trailer of the commit message with the given key, compared case-insensitively.
+fn trailer_increment_level(message: &str, key: &str) -> Option<IncrementLevel> {
+    git2::message_trailers_strs(message)
+        .ok()?
+        .iter()
+        .rev()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, value)| parse_increment_level(value.trim()))
+}
+
+/// Find the greatest increment level among "semver:<level>" labels, given as a JSON array of strings or a comma
+/// separated list. Returns `None` if the labels are malformed.
+fn increment_from_labels(labels: &str) -> Option<Option<IncrementLevel>> {
+    let labels = if labels.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<String>>(labels).ok()?
+    } else {
+        labels.split(',').map(str::to_string).collect()
+    };
+    Some(
+        labels
+            .iter()
+            .filter_map(|label| label.trim().strip_prefix(INCREMENT_LABEL_PREFIX))
+            .filter_map(parse_increment_level)
+            .max(),
+    )
+}
+
+/// The numbers of commits substituted into the prerelease format.
+#[derive(Clone, Copy, Debug, Default)]
+struct PrereleaseCounts {
+    /// The number of commits since the base version, or unique to HEAD under --relative-to-main, for "{count}".
+    count: usize,
+
+    /// The number of first-parent commits since the base version whose summaries name an increment level, as
+    /// [`level_counts`] counts them, for "{matched_count}".
+    matched_count: usize,
+}
+
+/// How the prerelease identifier is slugged and the prerelease cleaned of leading zeros.
+#[derive(Clone, Copy, Debug, Default)]
+struct SlugOptions<'a> {
+    /// Keep the identifier as given rather than slugging it, as under --no-slug.
+    no_slug: bool,
+
+    /// Characters besides alphanumerics to keep when slugging, as given by --slug-allow.
+    allow: &'a str,
+
+    /// Strip leading zeros from numeric identifiers rather than failing, as under --trim-leading-zeros.
+    trim_leading_zeros: bool,
+}
+
+impl<'a> SlugOptions<'a> {
+    fn new(options: &'a Options) -> Self {
+        Self {
+            no_slug: options.no_slug,
+            allow: options.slug_allow.as_deref().unwrap_or_default(),
+            trim_leading_zeros: options.trim_leading_zeros,
+        }
+    }
+}
+
+fn prerelease(
+    format: &str,
+    id: &str,
+    revision: &str,
+    counts: PrereleaseCounts,
+    slugging: SlugOptions,
+) -> Result<Prerelease, Error> {
+    let id_slug = if slugging.no_slug {
+        id.to_string()
+    } else {
+        slug(id, slugging.allow)
+    };
+    if id_slug.is_empty() {
+        return Err(if slugging.no_slug {
+            Error::InvalidPrereleaseIdentifier(id_slug)
+        } else {
+            Error::EmptyPrereleaseIdentifier(id.to_string())
+        });
+    }
+    let render = |id: &str, revision: &str| {
+        format
+            .replace("{count}", &counts.count.to_string())
+            .replace("{matched_count}", &counts.matched_count.to_string())
+            .replace("{id}", id)
+            .replace("{revision}", revision)
+    };
+    let (id_slug, revision) = if slugging.trim_leading_zeros {
+        (
+            trim_numeric_leading_zeros(&id_slug),
+            trim_numeric_leading_zeros(revision),
+        )
+    } else {
+        let pre = render(&id_slug, revision);
+        if pre != trim_numeric_leading_zeros(&pre) {
+            return Err(Error::LeadingZeroPrerelease(pre));
+        }
+        (id_slug, revision.to_string())
+    };
+    let revision = revision.as_str();
+    Prerelease::new(&id_slug).map_err(|_| Error::InvalidPrereleaseIdentifier(id_slug.clone()))?;
+    Prerelease::new(revision)
+        .map_err(|_| Error::InvalidPrereleaseRevision(revision.to_string()))?;
+    let pre = render(&id_slug, revision);
+    let pre = if slugging.trim_leading_zeros {
+        trim_numeric_leading_zeros(&pre)
+    } else {
+        pre
+    };
+    Prerelease::new(&pre).map_err(|_| Error::InvalidPrerelease(pre))
+}
+
+/// The increment levels named by the summaries of the first-parent commits from a commit down to, but excluding, the
+/// commit tagged with the base version, or down to the root commit if there is none, by authors --only-author admits.
+fn levels_since(
+    commit: &Commit,
+    base_commit: Option<Oid>,
+    commit_match_expression: &Regex,
+    options: &Options,
+) -> Vec<IncrementLevel> {
+    std::iter::successors(Some(commit.clone()), |commit| commit.parent(0).ok())
+        .take_while(|commit| Some(commit.id()) != base_commit)
+        .filter(|commit| admits_author(commit, options))
+        .filter_map(|commit| {
+            commit.summary().and_then(|summary| {
+                summary_increment_level(summary, commit_match_expression, options)
+            })
+        })
+        .collect()
+}
+
+/// Whether the author name or email of a commit matches --only-author, if given.
+fn admits_author(commit: &Commit, options: &Options) -> bool {
+    let Some(pattern) = &options.only_author else {
+        return true;
+    };
+    let author = commit.author();
+    let admitted = [author.name_bytes(), author.email_bytes()]
+        .into_iter()
+        .any(|value| glob_match(pattern, &String::from_utf8_lossy(value)));
+    admitted
+}
+
+/// The number of first-parent commits from a commit down to, but excluding, the commit tagged with the base version, or
+/// down to the root commit if there is none.
+fn commits_since(commit: &Commit, base_commit: Option<Oid>) -> usize {
+    std::iter::successors(Some(commit.clone()), |commit| commit.parent(0).ok())
+        .take_while(|commit| Some(commit.id()) != base_commit)
+        .count()
+}
+
+/// The number of commits reachable from a commit but not from an ancestor of it, through every parent, eg. those made on
+/// a branch since it forked off another.
+fn commits_unique_to(repository: &Repository, commit: Oid, ancestor: Oid) -> Result<usize, Error> {
+    let mut revwalk = repository.revwalk()?;
+    revwalk.push(commit)?;
+    revwalk.hide(ancestor)?;
+    Ok(revwalk.count())
+}
+
+/// Strip leading zeros from the numeric identifiers of a dot separated prerelease, leaving a lone "0" as is.
+fn trim_numeric_leading_zeros(pre: &str) -> String {
+    pre.split('.')
+        .map(|identifier| {
+            if identifier.len() > 1 && identifier.bytes().all(|b| b.is_ascii_digit()) {
+                match identifier.trim_start_matches('0') {
+                    "" => "0",
+                    trimmed => trimmed,
+                }
+            } else {
+                identifier
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Format seconds since the Unix epoch as YYYYMMDDHHMMSS in UTC.
+fn utc_timestamp(seconds: i64) -> String {
+    let (days, seconds) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    // Convert days since the epoch to a proleptic Gregorian date, counting years from March so the leap day comes last.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}{:02}{:02}{:02}",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Join the given build metadata with a build number, sanitizing the latter into valid build metadata.
+fn build_metadata(build: Option<&str>, number: Option<&str>) -> Result<BuildMetadata, Error> {
+    let build = build
+        .map(|build| {
+            BuildMetadata::new(build).map_err(|_| Error::InvalidBuildMetadata(build.to_string()))
+        })
+        .transpose()?;
+    let number = number.map(|number| {
+        number
+            .split('.')
+            .map(|identifier| {
+                identifier
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                    .collect::<String>()
+            })
+            .filter(|identifier| !identifier.is_empty())
+            .collect::<Vec<_>>()
+            .join(".")
+    });
+    let joined = [build.as_ref().map(BuildMetadata::as_str), number.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter(|identifiers| !identifiers.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    BuildMetadata::new(&joined).map_err(|_| Error::InvalidBuildMetadata(joined))
+}
+
+/// Replace every run of characters other than alphanumerics and the allowed ones with a single "-", trimming any at
+/// either end.
+fn slug(s: &str, allow: &str) -> String {
+    const TEMP_DELIM: char = ' ';
+    s.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c != TEMP_DELIM && allow.contains(c) {
+                c
+            } else {
+                TEMP_DELIM
+            }
+        })
+        .collect::<String>()
+        .split(TEMP_DELIM)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(
+            slug("//.hello////42349()*'']-=_+1`~world1----", ""),
+            "hello-42349-1-world1"
+        );
+        assert_eq!(slug("release/1.2.x", "."), "release-1.2.x");
+        assert_eq!(slug("release/1.2.x~rc", ".~"), "release-1.2.x~rc");
+        assert_eq!(slug("a b", " "), "a-b");
+    }
+
+    #[test]
+    fn test_prerelease() {
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "feature/thing",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap()
+            .as_str(),
+            "feature-thing.abc1234"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "//--//",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease identifier \"//--//\" is empty once slugged"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "café",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease identifier \"café\" is not a valid semver prerelease"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "feature",
+                "not valid!",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease revision \"not valid!\" is not a valid semver prerelease"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "01",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease \"01.abc1234\" has a numeric identifier with a leading zero, which semver forbids"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "rc",
+                "0.007",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease \"rc.0.007\" has a numeric identifier with a leading zero, which semver forbids"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "rc",
+                "0.007",
+                PrereleaseCounts::default(),
+                SlugOptions {
+                    trim_leading_zeros: true,
+                    ..SlugOptions::default()
+                }
+            )
+            .unwrap()
+            .as_str(),
+            "rc.0.7"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "release/01",
+                "00",
+                PrereleaseCounts::default(),
+                SlugOptions {
+                    trim_leading_zeros: true,
+                    ..SlugOptions::default()
+                }
+            )
+            .unwrap()
+            .as_str(),
+            "release-01.0"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "rc",
+                "0a1.10",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap()
+            .as_str(),
+            "rc.0a1.10"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{count}.{revision}",
+                "feature",
+                "abc1234",
+                PrereleaseCounts {
+                    count: 12,
+                    matched_count: 0,
+                },
+                SlugOptions::default()
+            )
+            .unwrap()
+            .as_str(),
+            "feature.12.abc1234"
+        );
+        assert_eq!(
+            prerelease(
+                "rc.{matched_count}.{count}",
+                "feature",
+                "abc1234",
+                PrereleaseCounts {
+                    count: 12,
+                    matched_count: 3,
+                },
+                SlugOptions::default()
+            )
+            .unwrap()
+            .as_str(),
+            "rc.3.12"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "rc1.2",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions {
+                    no_slug: true,
+                    ..SlugOptions::default()
+                }
+            )
+            .unwrap()
+            .as_str(),
+            "rc1.2.abc1234"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}.{revision}",
+                "feature/thing",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions {
+                    no_slug: true,
+                    ..SlugOptions::default()
+                }
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease identifier \"feature/thing\" is not a valid semver prerelease"
+        );
+        assert_eq!(
+            prerelease(
+                "{id}..{revision}",
+                "feature",
+                "abc1234",
+                PrereleaseCounts::default(),
+                SlugOptions::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "prerelease \"feature..abc1234\" is not a valid semver prerelease"
+        );
+    }
+
+    #[test]
+    fn test_net_increment() {
+        let base = Version::new(1, 2, 3);
+        assert_eq!(net_increment(&base, &base), None);
+        assert_eq!(
+            net_increment(&base, &Version::parse("1.2.3-feature.abc1234").unwrap()),
+            None
+        );
+        assert_eq!(
+            net_increment(&base, &Version::new(1, 2, 4)),
+            Some(IncrementLevel::Patch)
+        );
+        assert_eq!(
+            net_increment(&base, &Version::new(1, 3, 0)),
+            Some(IncrementLevel::Minor)
+        );
+        assert_eq!(
+            net_increment(&base, &Version::new(2, 0, 0)),
+            Some(IncrementLevel::Major)
+        );
+    }
+
+    #[test]
+    fn test_increment_from_labels() {
+        assert_eq!(
+            increment_from_labels(r#"["bug", "semver:minor"]"#),
+            Some(Some(IncrementLevel::Minor))
+        );
+        assert_eq!(
+            increment_from_labels("semver:patch, semver:Major,docs"),
+            Some(Some(IncrementLevel::Major))
+        );
+        assert_eq!(
+            increment_from_labels(r#"["semver:feature", "semver:fix"]"#),
+            Some(Some(IncrementLevel::Minor))
+        );
+        assert_eq!(
+            increment_from_labels("semver:bugfix,semver:BREAKING"),
+            Some(Some(IncrementLevel::Major))
+        );
+        assert_eq!(increment_from_labels("semver:none"), Some(None));
+        assert_eq!(increment_from_labels("bug,docs"), Some(None));
+        assert_eq!(increment_from_labels(""), Some(None));
+        assert_eq!(increment_from_labels("[\"semver:minor\""), None);
+    }
+
+    #[test]
+    fn test_build_metadata() {
+        assert_eq!(build_metadata(None, None).unwrap(), BuildMetadata::EMPTY);
+        assert_eq!(
+            build_metadata(Some("build"), Some("456")).unwrap().as_str(),
+            "build.456"
+        );
+        assert_eq!(
+            build_metadata(None, Some("run 7/attempt..2"))
+                .unwrap()
+                .as_str(),
+            "run-7-attempt.2"
+        );
+        assert_eq!(
+            build_metadata(Some("sha.abc"), Some("")).unwrap().as_str(),
+            "sha.abc"
+        );
+        assert_eq!(
+            build_metadata(Some("not valid"), None)
+                .unwrap_err()
+                .to_string(),
+            "build metadata \"not valid\" is not valid semver build metadata"
+        );
+    }
+
+    #[test]
+    fn test_increment_level_arguments() {
+        #[derive(Parser)]
+        struct Cli {
+            #[command(flatten)]
+            options: Options,
+        }
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from([&["git-semver"], args].concat()).map(|cli| cli.options)
+        };
+        for (value, level) in [
+            ("patch", IncrementLevel::Patch),
+            ("Patch", IncrementLevel::Patch),
+            ("fix", IncrementLevel::Patch),
+            ("BugFix", IncrementLevel::Patch),
+            ("minor", IncrementLevel::Minor),
+            ("MINOR", IncrementLevel::Minor),
+            ("feature", IncrementLevel::Minor),
+            ("Feature", IncrementLevel::Minor),
+            ("major", IncrementLevel::Major),
+            ("Major", IncrementLevel::Major),
+            ("breaking", IncrementLevel::Major),
+            ("BREAKING", IncrementLevel::Major),
+        ] {
+            assert_eq!(
+                parse(&["--increment", value]).unwrap().increment,
+                Some(level)
+            );
+            assert_eq!(
+                parse(&["--default-increment", value])
+                    .unwrap()
+                    .default_increment,
+                DefaultIncrement(Some(level))
+            );
+        }
+        assert!(parse(&["--increment", "huge"]).is_err());
+        assert_eq!(
+            parse(&[]).unwrap().default_increment,
+            DefaultIncrement::default()
+        );
+        for value in ["none", "None"] {
+            assert_eq!(
+                parse(&["--default-increment", value])
+                    .unwrap()
+                    .default_increment,
+                DefaultIncrement(None)
+            );
+        }
+        assert!(parse(&["--increment", "none"]).is_err());
+    }
+
+    #[test]
+    fn test_tag_name() {
+        let version = Version::parse("1.2.3-rc.1").unwrap();
+        let mut options = Options::default();
+        assert_eq!(tag_name(&version, &options).unwrap(), "1.2.3-rc.1");
+
+        options.tag_template = "{component}@{version}".to_string();
+        assert!(tag_name(&version, &options).is_err());
+        options.component = Some("packages/api".to_string());
+        assert_eq!(
+            tag_name(&version, &options).unwrap(),
+            "packages/api@1.2.3-rc.1"
+        );
+
+        options.tag_template = "{component}/v{version}".to_string();
+        options.component = Some("api".to_string());
+        assert_eq!(tag_name(&version, &options).unwrap(), "api/v1.2.3-rc.1");
+
+        options.tag_template = "release".to_string();
+        assert!(tag_name(&version, &options).is_err());
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(0), "19700101000000");
+        assert_eq!(utc_timestamp(951_782_400), "20000229000000");
+        assert_eq!(utc_timestamp(1_700_000_000), "20231114221320");
+        assert_eq!(utc_timestamp(4_102_444_799), "20991231235959");
+        assert_eq!(utc_timestamp(-1), "19691231235959");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*-nightly", "9.9.9-nightly"));
+        assert!(glob_match("*-nightly*", "9.9.9-nightly.3"));
+        assert!(!glob_match("*-nightly", "9.9.9-nightly.3"));
+        assert!(glob_match("1.?.*", "1.2.3"));
+        assert!(!glob_match("1.?.*", "1.23.4"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("v*.*.*", "v1.2.3"));
+        assert!(!glob_match("v*", "1.2.3"));
+    }
+
+    #[test]
+    fn test_trailer_increment_level() {
+        let message =
+            "Add thing\n\nSome details.\n\nBump: minor\nSigned-off-by: Me <me@example.com>\n";
+        assert_eq!(
+            trailer_increment_level(message, "Bump"),
+            Some(IncrementLevel::Minor)
+        );
+        assert_eq!(
+            trailer_increment_level(message, "bump"),
+            Some(IncrementLevel::Minor)
+        );
+        assert_eq!(trailer_increment_level(message, "Release"), None);
+        assert_eq!(
+            trailer_increment_level("Add thing\n\nBump: patch\nBump: Breaking\n", "Bump"),
+            Some(IncrementLevel::Major)
+        );
+        assert_eq!(
+            trailer_increment_level("Add thing\n\nBump: huge\n", "Bump"),
+            None
+        );
+        assert_eq!(trailer_increment_level("Bump: minor", "Bump"), None);
+    }
+
+    #[test]
+    fn test_branch_prefix_increment_level() {
+        let map = [
+            "feature=minor",
+            "bugfix=patch",
+            "hotfix=fix",
+            "breaking=major",
+        ]
+        .map(|value| parse_branch_prefix(value).unwrap());
+        for (summary, level) in [
+            ("Merge branch 'feature/thing'", Some(IncrementLevel::Minor)),
+            (
+                "Merge branch 'bugfix/typo' into main",
+                Some(IncrementLevel::Patch),
+            ),
+            (
+                "Merge pull request #1 from me/hotfix/crash",
+                Some(IncrementLevel::Patch),
+            ),
+            (
+                "Merge remote-tracking branch 'origin/breaking/api'",
+                Some(IncrementLevel::Major),
+            ),
+            ("Merge branch 'feature'", None),
+            ("Merge branch 'my-feature/thing'", None),
+            ("Merge branch 'other/thing'", None),
+        ] {
+            assert_eq!(
+                branch_prefix_increment_level(summary, &map),
+                level,
+                "{summary}"
+            );
+        }
+        assert!(parse_branch_prefix("feature").is_err());
+        assert!(parse_branch_prefix("=minor").is_err());
+        assert!(parse_branch_prefix("feature=huge").is_err());
+    }
+
+    #[test]
+    fn test_determine_increment_level() {
+        let default_expression = Regex::new(r"^Merge .*(patch|minor|major)/[\w-]+").unwrap();
+        assert_eq!(
+            determine_increment_level(
+                "Merge pull request #1 from me/minor/thing",
+                &default_expression
+            ),
+            Some(IncrementLevel::Minor)
+        );
+        assert_eq!(
+            determine_increment_level("Fix typo", &default_expression),
+            None
+        );
+
+        let named_expression =
+            Regex::new(r"^(Merge|Squash) (.*)(?P<level>patch|minor|major)/([\w-]+)").unwrap();
+        assert_eq!(
+            determine_increment_level("Squash branch major/thing", &named_expression),
+            Some(IncrementLevel::Major)
+        );
+        assert_eq!(
+            determine_increment_level("Merge branch patch/thing", &named_expression),
+            Some(IncrementLevel::Patch)
+        );
+
+        let unanchored_expression = Regex::new(r"(patch|minor|major)/[\w-]+").unwrap();
+        let squashed = "Merge minor/thing and major/other\n\nAlso patch/typo.";
+        assert_eq!(
+            determine_increment_level(squashed, &unanchored_expression),
+            Some(IncrementLevel::Minor)
+        );
+        assert_eq!(
+            determine_highest_increment_level(squashed, &unanchored_expression),
+            Some(IncrementLevel::Major)
+        );
+        assert_eq!(
+            determine_highest_increment_level("Fix typo", &unanchored_expression),
+            None
+        );
+    }
+
+    #[test]
+    fn test_user_message() {
+        let version = Version::new(2, 0, 0);
+        let ceiling = Version::new(1, 999, 999);
+        let cases = [
+            (Error::HeadWithSemverTag, "HEAD already tagged with semver"),
+            (
+                Error::CommitSummaryWithoutIncrementLevel,
+                "cannot derive version increment level from commit summary",
+            ),
+            (
+                Error::EmptyPrereleaseIdentifier("//".to_string()),
+                "prerelease identifier \"//\" is empty once slugged",
+            ),
+            (
+                Error::InvalidPrereleaseIdentifier("a b".to_string()),
+                "prerelease identifier \"a b\" is not a valid semver prerelease",
+            ),
+            (
+                Error::InvalidPrereleaseRevision("a b".to_string()),
+                "prerelease revision \"a b\" is not a valid semver prerelease",
+            ),
+            (
+                Error::LeadingZeroPrerelease("rc.01".to_string()),
+                "prerelease \"rc.01\" has a numeric identifier with a leading zero, which semver forbids",
+            ),
+            (
+                Error::InvalidPrerelease("a..b".to_string()),
+                "prerelease \"a..b\" is not a valid semver prerelease",
+            ),
+            (
+                Error::InvalidBuildMetadata("a b".to_string()),
+                "build metadata \"a b\" is not valid semver build metadata",
+            ),
+            (
+                Error::InvalidIncrementLabels("LABELS".to_string()),
+                "labels in environment variable LABELS are neither a JSON array of strings nor a comma separated list",
+            ),
+            (
+                Error::VersionExceedsCeiling { version, ceiling },
+                "version 2.0.0 exceeds the maximum version 1.999.999",
+            ),
+            (
+                Error::NotFirstParentAncestor {
+                    from: "1.0.0".to_string(),
+                    to: "HEAD".to_string(),
+                },
+                "1.0.0 is not a first-parent ancestor of HEAD",
+            ),
+            (
+                Error::MatchExpressionWithoutGroup("{from}".to_string()),
+                "match expression \"{from}\" has no capture group to read the increment level from",
+            ),
+            (
+                Error::RepositoryNotFound(git2::Error::from_str("not found")),
+                "not found",
+            ),
+            (
+                Error::MissingSignature(git2::Error::from_str("no user.name")),
+                "cannot create an annotated tag without a signature, configure user.name and user.email or create a lightweight tag: no user.name",
+            ),
+            (
+                Error::InvalidTagTemplate("{component}".to_string()),
+                "tag template \"{component}\" must have the version placeholder exactly once, and may only have the component placeholder along with a component",
+            ),
+            (
+                Error::InvalidBumpFile(".semver-bump".to_string()),
+                "bump file .semver-bump holds neither an increment level nor \"none\"",
+            ),
+            (
+                Error::VersionOverflow {
+                    version: Version::new(1, u64::MAX, 0),
+                    level: IncrementLevel::Minor,
+                },
+                "version 1.18446744073709551615.0 cannot be incremented by minor, its minor component is at the maximum",
+            ),
+            (
+                Error::DirtyWorkingTree(vec!["README.md".to_string(), "src/lib.rs".to_string()]),
+                "working tree has uncommitted changes to README.md, src/lib.rs, commit or stash them before tagging",
+            ),
+            (
+                Error::UnbornBranch("main".to_string()),
+                "HEAD is on branch main, which has no commits yet, commit something to compute a version",
+            ),
+            (
+                Error::DetachedHeadNotCommit(Oid::zero()),
+                "HEAD is detached at 0000000000000000000000000000000000000000, which is not a commit in this repository",
+            ),
+            (
+                Error::CorruptHead(git2::Error::from_str("bad HEAD")),
+                "HEAD cannot be read, the repository may be corrupt: bad HEAD",
+            ),
+            (
+                Error::InvalidMainCommit("origin/nope".to_string()),
+                "main commit \"origin/nope\" does not resolve to a commit in this repository",
+            ),
+            (
+                Error::TagVerification(io::Error::new(io::ErrorKind::NotFound, "no git")),
+                "cannot run git to verify tag signatures: no git",
+            ),
+            (Error::Git(git2::Error::from_str("boom")), "boom"),
+        ];
+        for (error, message) in cases {
+            assert_eq!(user_message(&error), message);
+            assert_eq!(error.to_string(), message);
+        }
+
+        let unclosed = String::from("(");
+        let regex_error = Regex::new(&unclosed).unwrap_err();
+        let message = regex_error.to_string();
+        assert_eq!(user_message(&Error::Regex(regex_error)), message);
+    }
+
+    #[test]
+    fn test_needs_release() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repository = Repository::init_opts(
+            dir.path(),
+            git2::RepositoryInitOptions::new().initial_head("main"),
+        )
+        .unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repository
+            .find_tree(repository.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let commit = |message| {
+            let parent = repository
+                .head()
+                .ok()
+                .map(|head| head.peel_to_commit().unwrap());
+            let oid = repository
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    message,
+                    &tree,
+                    &parent.iter().collect::<Vec<_>>(),
+                )
+                .unwrap();
+            repository.find_object(oid, None).unwrap()
+        };
+        let options = Options::default();
+
+        let first = commit("Initial commit");
+        assert!(needs_release(&repository, &options).unwrap());
+        repository.tag_lightweight("1.0.0", &first, false).unwrap();
+        assert!(!needs_release(&repository, &options).unwrap());
+        // Short-circuited even where the computation would fail.
+        let invalid = Options {
+            match_expression: "^Merge".to_string(),
+            ..Options::default()
+        };
+        assert!(!needs_release(&repository, &invalid).unwrap());
+
+        let second = commit("Fix typo");
+        assert!(needs_release(&repository, &options).unwrap());
+        let unchanged = Options {
+            default_increment: DefaultIncrement(None),
+            ..Options::default()
+        };
+        assert!(!needs_release(&repository, &unchanged).unwrap());
+        repository
+            .tag_lightweight("1.0.1-rc.1", &second, false)
+            .unwrap();
+        assert!(!needs_release(&repository, &options).unwrap());
+        let stable = Options {
+            base: Base::Stable,
+            ..Options::default()
+        };
+        assert!(needs_release(&repository, &stable).unwrap());
+
+        repository
+            .branch("feature", &second.peel_to_commit().unwrap(), false)
+            .unwrap();
+        repository.set_head("refs/heads/feature").unwrap();
+        commit("Add thing");
+        assert!(!needs_release(&repository, &options).unwrap());
+    }
+}
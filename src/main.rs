@@ -1,182 +1,946 @@
 use std::{
-    char,
-    collections::{HashMap, VecDeque},
-    error,
-    fmt::{Debug, Display},
+    collections::BTreeMap,
+    env, error,
+    fmt::{self, Display},
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::{self, ExitCode},
 };
 
-use git2::{IntoCString, Oid, Reference};
-use regex::Regex;
-use semver_extra::{semver::Version, Increment, IncrementLevel};
-
-use clap::Parser;
+use clap::{
+    error::ErrorKind, parser::ValueSource, Args, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
+use git_semversion::{
+    increment_name, ChangelogSection, Error, LevelCounts, Options, Output, TagMap,
+};
+use semver::Version;
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::json;
 
 #[derive(Debug, Parser)]
 #[command(author, version)]
 /// Generate a semantic versioning compliant tag for your HEAD commit.
 struct Cli {
-    /// The name of your repository's main branch. Useful if you continue to use "master" or "trunk".
-    #[arg(short, long, default_value = "main")]
-    main_branch: String,
+    #[command(flatten)]
+    repository: RepositoryArgs,
+
+    /// Print the effective options as JSON to stderr, along with the repository found and the main branch chosen among the --main-branch candidates, and exit without computing a version.
+    #[arg(long, global = true)]
+    show_config: bool,
+
+    /// When to color the error and warning labels on stderr, in auto mode only if stderr is a terminal and NO_COLOR is unset. The result on stdout is never colored.
+    #[arg(long, value_enum, global = true, default_value_t = Color::Auto)]
+    color: Color,
+
+    /// Suppress warnings, such as when several --main-branch candidates exist, the computed version is already tagged on another commit or a computed prerelease orders below its already tagged release.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Fail with exit code 11 rather than warn when the computed version is already tagged on another commit, eg. by a parallel branch, before it is tagged again, and with exit code 14 when a computed prerelease orders below its release already tagged on another commit, eg. "1.2.0-feature" of a branch behind "1.2.0".
+    #[arg(long, global = true)]
+    strict: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    compute: ComputeArgs,
+}
+
+#[derive(Debug, Args)]
+struct RepositoryArgs {
+    /// Path to the repository, taking precedence over the GIT_DIR environment variable and discovery from the current directory.
+    #[arg(short = 'C', long, global = true)]
+    repository: Option<PathBuf>,
+
+    /// Skip git's repository ownership checks, as if safe.directory were "*", for this run only. Useful in containers where the repository is owned by another user.
+    #[arg(long, global = true)]
+    trust_repo: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the version for HEAD. This is the default when no subcommand is given.
+    Compute(ComputeArgs),
+
+    /// Tag HEAD with its version, optionally pushing the tag to a remote, and print the version.
+    Tag(TagArgs),
+
+    /// Check that a version is the one computed for HEAD, eg. the version declared in a package manifest.
+    Validate(ValidateArgs),
+
+    /// List the semver tagged versions reachable from HEAD in ascending order.
+    List(ListArgs),
+}
+
+#[derive(Debug, Args)]
+struct ComputeArgs {
+    /// Format in which to print the result, either the single field selected by --show as text or every field as JSON or YAML.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Field of the result to print in text format. The increment is the net increment applied to the base version, or "none", the base commit is the commit tagged with the base version, or "none", the base tag is the name of its tag as found, eg. "v1.2.3", or "none", and the prerelease is the dot separated prerelease identifiers of the version, or an empty line for a version without one.
+    #[arg(short, long, value_enum, default_value_t = Show::Version)]
+    show: Show,
+
+    /// Exit with code 10, printing nothing, when HEAD is already tagged with the greatest reachable version, meaning there is nothing to release.
+    #[arg(long)]
+    check: bool,
+
+    /// For every semver tag reachable from HEAD, print a JSON line with the tag, the commit it points at, and the version computed for that commit disregarding its own tags, ie. the version it would have been tagged with.
+    #[arg(long, conflicts_with = "check")]
+    bulk: bool,
+
+    /// Print the greatest version without a prerelease tagged on a commit reachable from HEAD, ie. the latest release, rather than computing the next version. Exits with code 8 if there is none.
+    #[arg(long, conflicts_with_all = ["check", "bulk", "exec"])]
+    latest_stable: bool,
 
-    /// Identifier to use for prerelease during non-main branch execution, using branch name slug when omitted.
+    /// Print the version for each of the patch, minor and major increment levels as the base version found for HEAD would be incremented, as a table in text format or an object keyed by increment level in JSON format.
+    #[arg(long, conflicts_with_all = ["show", "check", "bulk", "latest_stable", "from", "staged", "exec"])]
+    preview_all: bool,
+
+    /// Revision to take as the base, as if it were the only one tagged, eg. a tag or commit among the first-parent ancestors of --to. Its version is that of its greatest tag, otherwise the version computed for it.
+    #[arg(long, value_name = "REV", conflicts_with_all = ["check", "bulk", "latest_stable"])]
+    from: Option<String>,
+
+    /// Revision to compute the version of relative to --from, in place of HEAD.
+    #[arg(long, value_name = "REV", requires = "from")]
+    to: Option<String>,
+
+    /// Preview the version of a commit of the staged changes on top of HEAD with the summary given by --summary, as it would be released from the main branch, eg. in a pre-commit hook. A tag on HEAD is taken as the base.
+    #[arg(long, requires = "summary", conflicts_with_all = ["check", "bulk", "latest_stable", "from"])]
+    staged: bool,
+
+    /// Summary of the commit previewed by --staged, deciding its increment as the summary of a merge commit would, otherwise --default-increment applying.
+    #[arg(long, value_name = "TEXT", requires = "staged")]
+    summary: Option<String>,
+
+    /// Template in which to print the version in text format, with "{major}", "{minor}" and "{patch}" substituted, and "{pre}" and "{build}" substituted with the prerelease and build metadata preceded by "-" and "+" respectively, or nothing if empty. Eg. "{major}.{minor}.{patch}" drops any prerelease and build metadata. The result need not be semver.
+    #[arg(long, value_name = "TEMPLATE")]
+    version_format: Option<String>,
+
+    /// Width to which to zero-pad the major, minor and patch components of the version in text format, eg. 3 for "001.004.002". Only the printed version is padded, tags being created as valid semver.
+    #[arg(long, value_name = "WIDTH")]
+    pad: Option<usize>,
+
+    /// Add a "changelog" to the result in JSON or YAML format, listing the first-parent commits since the base version grouped by the increment level their summary names, from major down to "none", eg. for a release bot to get the version and its release notes in one call.
+    #[arg(long, conflicts_with_all = ["bulk", "latest_stable", "preview_all", "from", "staged"])]
+    changelog: bool,
+
+    /// Add "counts" to the result in JSON or YAML format, the number of first-parent commits since the base version naming each increment level, as for --changelog, and "skipped" for those naming none. --show counts prints them as text.
+    #[arg(long, conflicts_with_all = ["bulk", "latest_stable", "preview_all", "from", "staged"])]
+    counts: bool,
+
+    /// Print the result without a trailing newline.
     #[arg(short, long)]
-    prerelease_id: Option<String>,
+    no_newline: bool,
+
+    /// Shell command to run once the version is printed, with "{version}" and "{tag}", the name of the tag for the version, substituted, eg. "cargo set-version {version}". Exits with the command's exit code.
+    #[arg(long, value_name = "COMMAND", conflicts_with = "bulk")]
+    exec: Option<String>,
+
+    /// Append the version, the name of its tag, whether it is a prerelease and the net increment, as "version", "tag", "is_prerelease" and "bump" outputs, to the file named by the GITHUB_OUTPUT environment variable in GitHub Actions. Warns and does nothing else if GITHUB_OUTPUT is unset.
+    #[arg(long, conflicts_with_all = ["bulk", "latest_stable", "preview_all"])]
+    github_output: bool,
+
+    /// Dump the walk for the base version of HEAD as JSON before computing the version, with each visited commit's parents, summary, matched increment level and tags, and whether HEAD is on the main branch, eg. to attach to a bug report.
+    #[arg(long, conflicts_with_all = ["bulk", "from"])]
+    trace_walk: bool,
 
-    /// Revision to use for prerelease during non-main branch execution, using short commit hash when omitted.
-    #[arg(short = 'r', long)]
-    prerelease_revision: Option<String>,
+    /// File to write the --trace-walk dump to rather than stderr.
+    #[arg(long, value_name = "PATH", requires = "trace_walk")]
+    trace_file: Option<PathBuf>,
 
-    /// Explicit increment level override for use during main branch execution, forcing to ignore the increment level derived from commit summary.
+    /// Print the commits of the walk for the base version of HEAD as CSV with a "short_hash,summary,level" header, the level being the increment level matched in the summary, or "none", rather than the version, eg. to analyze release cadence in a spreadsheet.
+    #[arg(long, conflicts_with_all = ["bulk", "latest_stable", "preview_all", "from", "staged"])]
+    audit_csv: bool,
+
+    /// File to write the --audit-csv rows to, the version being printed as usual.
+    #[arg(long, value_name = "PATH", requires = "audit_csv")]
+    audit_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: Options,
+}
+
+#[derive(Debug, Args)]
+struct TagArgs {
+    /// Create an annotated tag, with the version as its message unless --message is given, rather than a lightweight tag. Requires user.name and user.email to be configured.
     #[arg(short, long)]
-    increment: Option<IncrementLevel>,
+    annotate: bool,
+
+    /// Message of the annotated tag, implying --annotate. "{changelog}" is substituted with the changelog of the release, as --changelog lists it, one "- <short hash> <summary>" line per commit under a heading for each increment level.
+    #[arg(long)]
+    message: Option<String>,
+
+    /// File to read the message of the annotated tag from, as for --message.
+    #[arg(long, value_name = "PATH", conflicts_with = "message")]
+    message_file: Option<PathBuf>,
 
-    /// Increment level override for non-merge commits to main branch, ie. commits directly to main branch.
-    #[arg(long, default_value_t = IncrementLevel::Patch)]
-    default_increment: IncrementLevel,
+    /// Remote to push the tag to once created, eg. "origin".
+    #[arg(long, value_name = "REMOTE")]
+    push: Option<String>,
 
-    /// Regular expression to match the increment level in the commit summary of a commit to the main branch.
-    #[arg(
-        short = 'e',
-        long,
-        default_value = r"^Merge .*(patch|minor|major)/[\w-]+"
-    )]
-    match_expression: String,
+    /// Fail with exit code 12, before tagging, if the working tree or index has uncommitted changes to tracked files, so that the tag captures exactly what is checked out.
+    #[arg(long)]
+    require_clean: bool,
+
+    /// Print the tag that would be created, the commit it would target, whether it would be annotated and the remote it would be pushed to, without creating or pushing it. The version is still printed.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    options: Options,
+}
+
+#[derive(Debug, Args)]
+struct ValidateArgs {
+    /// Version expected to be computed for HEAD.
+    version: Version,
+
+    #[command(flatten)]
+    options: Options,
 }
 
-#[derive(Clone, Copy)]
-enum Error {
-    HeadWithSemverTag,
-    CommitSummaryWithoutIncrementLevel,
+#[derive(Debug, Args)]
+struct ListArgs {
+    #[command(flatten)]
+    options: Options,
 }
 
-impl Debug for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Error(\"")?;
-        Display::fmt(self, f)?;
-        f.write_str("\")")?;
-        Ok(())
+/// Exit code under --check when HEAD is already tagged with the greatest reachable version.
+const UP_TO_DATE_EXIT_CODE: u8 = 10;
+
+/// Exit code of validate when the given version is not the one computed for HEAD.
+const MISMATCH_EXIT_CODE: u8 = 7;
+
+/// Exit code under --latest-stable when no version without a prerelease is reachable from HEAD.
+const NO_STABLE_VERSION_EXIT_CODE: u8 = 8;
+
+/// Exit code under --strict when the computed version is already tagged on another commit.
+const TAGGED_ELSEWHERE_EXIT_CODE: u8 = 11;
+
+/// Exit code under --strict when the computed prerelease orders below its release already tagged on another commit.
+const SHADOWED_RELEASE_EXIT_CODE: u8 = 14;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Yaml,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Show {
+    Version,
+    Increment,
+    BaseCommit,
+    BaseTag,
+    Prerelease,
+    Counts,
+}
+
+fn main() -> ExitCode {
+    let cli = parse();
+    let diagnostics = Diagnostics::new(cli.color, cli.quiet, cli.strict);
+
+    match run(cli, diagnostics) {
+        Ok(code) => code,
+        Err(error) => {
+            diagnostics.error(format_args!("{error:?}"));
+            ExitCode::from(error.downcast_ref().map_or(1, Error::exit_code))
+        }
     }
 }
 
-impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::HeadWithSemverTag => f.write_str("HEAD already tagged with semver"),
-            Error::CommitSummaryWithoutIncrementLevel => {
-                f.write_str("cannot derive version increment level from commit summary")
-            }
+/// Parse the command line, rejecting the options of the default compute subcommand alongside another subcommand. Clap's
+/// args_conflicts_with_subcommands would reject the global options too.
+fn parse() -> Cli {
+    let mut command = Cli::command();
+    let matches = command.get_matches_mut();
+    if let Some((name, _)) = matches.subcommand() {
+        let compute = ComputeArgs::augment_args(clap::Command::new("compute"));
+        let flag = compute
+            .get_arguments()
+            .find(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+            .map(|arg| arg.get_long().unwrap_or(arg.get_id().as_str()).to_string());
+        if let Some(flag) = flag {
+            command
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("the subcommand '{name}' cannot be used with '--{flag}'"),
+                )
+                .exit();
         }
     }
+    Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit())
 }
 
-impl error::Error for Error {}
+/// Prints labelled diagnostics to stderr, colored or not.
+#[derive(Clone, Copy, Debug)]
+struct Diagnostics {
+    colored: bool,
+    quiet: bool,
+    strict: bool,
+}
 
-fn main() -> Result<(), Box<dyn error::Error>> {
-    let cli = Cli::parse();
+impl Diagnostics {
+    fn new(color: Color, quiet: bool, strict: bool) -> Self {
+        let colored = match color {
+            Color::Auto => {
+                io::stderr().is_terminal()
+                    && !env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+            }
+            Color::Always => true,
+            Color::Never => false,
+        };
+        Self {
+            colored,
+            quiet,
+            strict,
+        }
+    }
 
-    git2::Config::open_default()?.set_str("safe.directory", "*")?;
+    fn error(self, message: impl Display) {
+        self.print("Error", "1;31", message);
+    }
 
-    let repository = git2::Repository::open_from_env()?;
+    fn warning(self, message: impl Display) {
+        if !self.quiet {
+            self.print("Warning", "1;33", message);
+        }
+    }
 
-    let head = repository.head()?;
+    /// Report a likely mistake as a warning, or as an error under --strict, returning whether to fail.
+    fn caution(self, message: impl Display) -> bool {
+        if self.strict {
+            self.error(message);
+        } else {
+            self.warning(message);
+        }
+        self.strict
+    }
 
-    let head_commit = head.peel_to_commit()?;
+    fn print(self, label: &str, sgr: &str, message: impl Display) {
+        if self.colored {
+            eprintln!("\x1b[{sgr}m{label}:\x1b[0m {message}");
+        } else {
+            eprintln!("{label}: {message}");
+        }
+    }
+}
+
+fn run(cli: Cli, diagnostics: Diagnostics) -> Result<ExitCode, Box<dyn error::Error>> {
+    if cli.repository.trust_repo {
+        // SAFETY: No other threads exist yet to be using libgit2 concurrently.
+        // Skipping the ownership check in this process is equivalent to setting safe.directory to "*" without writing to
+        // the user's git config.
+        unsafe { git2::opts::set_verify_owner_validation(false)? };
+    }
 
-    let head_shorthand = head.shorthand_bytes().into_c_string()?.into_string()?;
-    let head_short_id = head_commit
-        .as_object()
-        .short_id()?
-        .into_c_string()?
-        .into_string()?;
+    let repository = git_semversion::open(cli.repository.repository.as_deref())?;
 
-    let commit_match_expression = Regex::new(cli.match_expression.as_str())?;
+    let command = cli.command.unwrap_or(Command::Compute(cli.compute));
 
-    let tags: HashMap<Oid, Version> = repository
-        .references()?
-        .flatten()
-        .filter(Reference::is_tag)
-        .filter_map(|reference| {
-            let tag_target = reference.peel_to_tag().map(|tag| tag.target_id());
-            let target = reference.target();
-            let shorthand = reference.shorthand().map(Version::parse);
-            match (tag_target, target, shorthand) {
-                (Ok(tag_target), Some(target), Some(Ok(shorthand))) => {
-                    Some(vec![(tag_target, shorthand.clone()), (target, shorthand)])
+    let options = match &command {
+        Command::Compute(ComputeArgs { options, .. })
+        | Command::Tag(TagArgs { options, .. })
+        | Command::Validate(ValidateArgs { options, .. })
+        | Command::List(ListArgs { options }) => options,
+    };
+    let main_branches = git_semversion::existing_main_branches(&repository, options)?;
+    if cli.show_config {
+        let config = json!({
+            "repository": repository.workdir().unwrap_or(repository.path()),
+            "main_branch": main_branches.first(),
+            "options": options,
+        });
+        eprintln!("{config}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    if main_branches.len() > 1 && options.main_commit.is_none() {
+        diagnostics.warning(format_args!(
+            "several main branch candidates exist, {}, using {}",
+            main_branches.join(", "),
+            main_branches[0]
+        ));
+    }
+
+    match command {
+        Command::Compute(args) => compute(&repository, args, diagnostics),
+        Command::Tag(args) => {
+            if args.require_clean {
+                git_semversion::require_clean(&repository)?;
+            }
+            let tags = git_semversion::tags(&repository, &args.options)?;
+            let output = git_semversion::compute_with_tags(&repository, &tags, &args.options)?;
+            if tagged_elsewhere(&output, &tags, diagnostics) {
+                return Ok(ExitCode::from(TAGGED_ELSEWHERE_EXIT_CODE));
+            }
+            if shadowed_release(&output, &tags, diagnostics) {
+                return Ok(ExitCode::from(SHADOWED_RELEASE_EXIT_CODE));
+            }
+            let message = match args.message_file {
+                Some(path) => Some(fs::read_to_string(path)?),
+                None => args.message,
+            };
+            let message = match (message, args.annotate) {
+                (Some(message), _) if message.contains("{changelog}") => {
+                    let changelog =
+                        git_semversion::changelog_with_tags(&repository, &tags, &args.options)?;
+                    Some(message.replace("{changelog}", &changelog_text(&repository, &changelog)?))
                 }
-                (Ok(tag_target), _, Some(Ok(shorthand))) => Some(vec![(tag_target, shorthand)]),
-                (_, Some(target), Some(Ok(shorthand))) => Some(vec![(target, shorthand)]),
-                _ => None,
+                (Some(message), _) => Some(message),
+                (None, true) => Some(output.version.to_string()),
+                (None, false) => None,
+            };
+            let name = git_semversion::tag_name(&output.version, &args.options)?;
+            if args.dry_run {
+                eprintln!(
+                    "would create {} tag {name} on commit {}",
+                    if message.is_some() {
+                        "annotated"
+                    } else {
+                        "lightweight"
+                    },
+                    output.head_commit
+                );
+                if let Some(remote) = args.push {
+                    eprintln!("would push tag {name} to remote {remote}");
+                }
+            } else {
+                git_semversion::create_tag(&repository, &name, message.as_deref())?;
+                if let Some(remote) = args.push {
+                    git_semversion::push_tag(&repository, &remote, &name)?;
+                }
+            }
+            println!("{}", output.version);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Validate(args) => {
+            let output = git_semversion::compute(&repository, &args.options)?;
+            if output.version == args.version {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                diagnostics.error(format_args!(
+                    "{} is not the version computed for HEAD, {}",
+                    args.version, output.version
+                ));
+                Ok(ExitCode::from(MISMATCH_EXIT_CODE))
+            }
+        }
+        Command::List(args) => {
+            for (_, version) in
+                git_semversion::reachable_versions(&repository, "HEAD", &args.options)?
+            {
+                println!("{version}");
             }
-        })
-        .flatten()
-        .collect();
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
 
-    let mut tag = Version::new(0, 0, 0);
+fn compute(
+    repository: &git2::Repository,
+    args: ComputeArgs,
+    diagnostics: Diagnostics,
+) -> Result<ExitCode, Box<dyn error::Error>> {
+    let tags = git_semversion::tags(repository, &args.options)?;
 
-    let mut commits = VecDeque::from([head.peel_to_commit()?]);
+    if args.trace_walk {
+        let trace = serde_json::to_string(&git_semversion::trace_walk_with_tags(
+            repository,
+            &tags,
+            &args.options,
+        )?)?;
+        match &args.trace_file {
+            Some(path) => fs::write(path, trace + "\n")?,
+            None => eprintln!("{trace}"),
+        }
+    }
 
-    while let Some(commit) = commits.pop_front() {
-        if let Some(t) = tags.get(&commit.id()) {
-            if head.target().map(|c| c == commit.id()).unwrap_or_default() {
-                return Err(Error::HeadWithSemverTag.into());
+    if args.audit_csv {
+        let csv = audit_csv(repository, &tags, &args.options)?;
+        match &args.audit_file {
+            Some(path) => fs::write(path, csv)?,
+            None => {
+                print!("{csv}");
+                return Ok(ExitCode::SUCCESS);
             }
-            tag = t.clone();
-            break;
         }
-        if let Ok(parent_id) = commit.parent(0) {
-            commits.push_back(parent_id);
+    }
+
+    if args.bulk {
+        for entry in git_semversion::bulk_with_tags(repository, &tags, &args.options)? {
+            let line = match entry.next {
+                Ok(output) => json!({
+                    "tag": entry.tag,
+                    "commit": entry.commit.to_string(),
+                    "next": output.version,
+                }),
+                Err(error) => json!({
+                    "tag": entry.tag,
+                    "commit": entry.commit.to_string(),
+                    "error": error.to_string(),
+                }),
+            };
+            println!("{line}");
         }
+        return Ok(ExitCode::SUCCESS);
     }
 
-    if head_shorthand == cli.main_branch {
-        if let Some(increment) = cli.increment {
-            tag.increment(increment);
-        } else if head_commit.parent(1).is_ok() {
-            let head_summary = head_commit
-                .summary()
-                .ok_or(Error::CommitSummaryWithoutIncrementLevel)?;
-            let increment_level = &commit_match_expression
-                .captures(head_summary)
-                .ok_or(Error::CommitSummaryWithoutIncrementLevel)?[1]
-                .parse::<IncrementLevel>()?;
-            tag.increment(*increment_level);
-        } else {
-            tag.increment(cli.default_increment);
+    if args.latest_stable {
+        let Some(version) = git_semversion::latest_stable_version_with_tags(repository, &tags)?
+        else {
+            diagnostics.error("no version without a prerelease is reachable from HEAD");
+            return Ok(ExitCode::from(NO_STABLE_VERSION_EXIT_CODE));
+        };
+        print(&format_version(&version, &args), args.no_newline)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.preview_all {
+        let previews =
+            git_semversion::preview_increments_with_tags(repository, &tags, &args.options)?;
+        let result = match args.format {
+            Format::Text => previews
+                .iter()
+                .map(|(level, version)| format!("{level} {}", format_version(version, &args)))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Format::Json | Format::Yaml => {
+                // Keyed in sorted order, whatever order serde_json's own maps keep, for the output to be diffable.
+                let previews = BTreeMap::from_iter(
+                    previews
+                        .into_iter()
+                        .map(|(level, version)| (level.to_string(), version)),
+                );
+                match args.format {
+                    Format::Json => serde_json::to_string(&previews)?,
+                    _ => yaml(&previews)?,
+                }
+            }
+        };
+        print(&result, args.no_newline)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let result = match (&args.from, &args.summary) {
+        (Some(from), _) => git_semversion::compute_between_with_tags(
+            repository,
+            from,
+            args.to.as_deref().unwrap_or("HEAD"),
+            &tags,
+            &args.options,
+        ),
+        (None, Some(summary)) => {
+            git_semversion::compute_staged_with_tags(repository, summary, &tags, &args.options)
         }
+        (None, None) => git_semversion::compute_with_tags(repository, &tags, &args.options),
+    };
+    let output = match result {
+        Err(Error::HeadWithSemverTag)
+            if args.check
+                && git_semversion::head_has_latest_version_with_tags(repository, &tags)? =>
+        {
+            return Ok(ExitCode::from(UP_TO_DATE_EXIT_CODE));
+        }
+        result => result?,
+    };
+    if tagged_elsewhere(&output, &tags, diagnostics) {
+        return Ok(ExitCode::from(TAGGED_ELSEWHERE_EXIT_CODE));
+    }
+    if shadowed_release(&output, &tags, diagnostics) {
+        return Ok(ExitCode::from(SHADOWED_RELEASE_EXIT_CODE));
+    }
+
+    let changelog = if args.changelog {
+        Some(git_semversion::changelog_with_tags(
+            repository,
+            &tags,
+            &args.options,
+        )?)
+    } else {
+        None
+    };
+    let counts = if args.counts || matches!((args.format, args.show), (Format::Text, Show::Counts))
+    {
+        Some(git_semversion::level_counts_with_tags(
+            repository,
+            &tags,
+            &args.options,
+        )?)
     } else {
-        tag.pre = semver_extra::semver::Prerelease::new(&format!(
-            "{}.{}",
-            slug(&cli.prerelease_id.unwrap_or(head_shorthand)),
-            cli.prerelease_revision.unwrap_or(head_short_id)
-        ))?;
+        None
+    };
+    print(&render(&output, changelog, counts, &args)?, args.no_newline)?;
+
+    if args.github_output {
+        github_output(&output, &args.options, diagnostics)?;
+    }
+
+    match args.exec {
+        Some(command) => exec(
+            &command,
+            &output,
+            &git_semversion::tag_name(&output.version, &args.options)?,
+        ),
+        None => Ok(ExitCode::SUCCESS),
+    }
+}
+
+/// Caution that the computed version is already tagged on a commit other than the one it was computed for, returning
+/// whether to fail.
+fn tagged_elsewhere(output: &Output, tags: &TagMap, diagnostics: Diagnostics) -> bool {
+    match git_semversion::tagged_elsewhere_with_tags(&output.version, output.head_commit, tags) {
+        Some(commit) => diagnostics.caution(format_args!(
+            "version {} is already tagged on commit {commit}",
+            output.version
+        )),
+        None => false,
+    }
+}
+
+/// Warn, or fail under --strict, when the computed prerelease orders below its release already tagged on another commit.
+fn shadowed_release(output: &Output, tags: &TagMap, diagnostics: Diagnostics) -> bool {
+    match git_semversion::shadowed_release_with_tags(output, tags) {
+        Some(commit) => diagnostics.caution(format_args!(
+            "prerelease {} orders below release {}.{}.{} already tagged on commit {commit}, the branch is likely behind the release line",
+            output.version, output.version.major, output.version.minor, output.version.patch
+        )),
+        None => false,
+    }
+}
+
+/// The commits of the walk for the base version of HEAD as CSV rows of their short hash, summary and increment level.
+fn audit_csv(
+    repository: &git2::Repository,
+    tags: &TagMap,
+    options: &Options,
+) -> Result<String, Box<dyn error::Error>> {
+    let mut csv = String::from("short_hash,summary,level\n");
+    for step in git_semversion::trace_walk_with_tags(repository, tags, options)?.commits {
+        let short_hash = repository.find_object(step.commit, None)?.short_id()?;
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            String::from_utf8_lossy(&short_hash),
+            csv_field(step.summary.as_deref().unwrap_or_default()),
+            increment_name(step.level)
+        ));
     }
+    Ok(csv)
+}
 
-    println!("{tag}");
+/// Quote a CSV field if it holds a delimiter, quote or line break, doubling any quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
 
+/// Append the result as step outputs to the GITHUB_OUTPUT file, warning if there is none.
+fn github_output(
+    output: &Output,
+    options: &Options,
+    diagnostics: Diagnostics,
+) -> Result<(), Box<dyn error::Error>> {
+    let Some(path) = env::var_os("GITHUB_OUTPUT").filter(|path| !path.is_empty()) else {
+        diagnostics.warning("--github-output given but GITHUB_OUTPUT is unset, writing no outputs");
+        return Ok(());
+    };
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "version={}", output.version)?;
+    writeln!(
+        file,
+        "tag={}",
+        git_semversion::tag_name(&output.version, options)?
+    )?;
+    writeln!(file, "is_prerelease={}", !output.version.pre.is_empty())?;
+    writeln!(file, "bump={}", increment_name(output.increment))?;
     Ok(())
 }
 
-fn slug(s: &str) -> String {
-    const TEMP_DELIM: char = ' ';
-    s.chars()
-        .map(|c| if c.is_alphanumeric() { c } else { TEMP_DELIM })
-        .collect::<String>()
-        .split(TEMP_DELIM)
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>()
-        .join("-")
+fn print(result: &str, no_newline: bool) -> io::Result<()> {
+    if no_newline {
+        print!("{result}");
+        io::stdout().flush()
+    } else {
+        println!("{result}");
+        Ok(())
+    }
+}
+
+/// The changelog as plain text for a tag message, a heading per increment level followed by a line per commit.
+fn changelog_text(
+    repository: &git2::Repository,
+    changelog: &[ChangelogSection],
+) -> Result<String, git2::Error> {
+    let mut sections = Vec::new();
+    for section in changelog {
+        let mut lines = vec![format!("{}:", increment_name(section.level))];
+        for entry in &section.commits {
+            let short_id = repository.find_object(entry.commit, None)?.short_id()?;
+            lines.push(format!(
+                "- {} {}",
+                short_id.as_str().unwrap_or_default(),
+                entry.summary.as_deref().unwrap_or_default()
+            ));
+        }
+        sections.push(lines.join("\n"));
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// Run a shell command with the version substituted, forwarding its exit code.
+fn exec(command: &str, output: &Output, tag: &str) -> Result<ExitCode, Box<dyn error::Error>> {
+    let command = command
+        .replace("{version}", &output.version.to_string())
+        .replace("{tag}", tag);
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let status = process::Command::new(shell)
+        .args([flag, &command])
+        .status()?;
+    // A command killed by a signal has no exit code, so report it as a plain failure.
+    Ok(ExitCode::from(
+        status.code().map_or(1, |code| code.clamp(0, 255) as u8),
+    ))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn render(
+    output: &Output,
+    changelog: Option<Vec<ChangelogSection>>,
+    counts: Option<LevelCounts>,
+    args: &ComputeArgs,
+) -> Result<String, Box<dyn error::Error>> {
+    /// The result followed by its changelog and level counts, if any.
+    #[derive(Serialize)]
+    struct Document<'a> {
+        #[serde(flatten)]
+        output: &'a Output,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        changelog: Option<Vec<ChangelogSection>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        counts: Option<LevelCounts>,
+    }
+    let document = Document {
+        output,
+        changelog,
+        counts: counts.filter(|_| args.counts),
+    };
+    Ok(match (args.format, args.show) {
+        (Format::Json, _) => serde_json::to_string(&document)?,
+        (Format::Yaml, _) => yaml(&document)?,
+        (Format::Text, Show::Version) => format_version(&output.version, args),
+        (Format::Text, Show::Increment) => increment_name(output.increment),
+        (Format::Text, Show::BaseCommit) => output
+            .base_commit
+            .map_or_else(|| "none".to_string(), |oid| oid.to_string()),
+        (Format::Text, Show::BaseTag) => output
+            .base_tag
+            .clone()
+            .unwrap_or_else(|| "none".to_string()),
+        (Format::Text, Show::Prerelease) => output.version.pre.to_string(),
+        (Format::Text, Show::Counts) => {
+            let counts = counts.unwrap_or_default();
+            format!(
+                "major={} minor={} patch={} skipped={}",
+                counts.major, counts.minor, counts.patch, counts.skipped
+            )
+        }
+    })
+}
 
-    #[test]
-    fn test_slug() {
-        assert_eq!(
-            slug("//.hello////42349()*'']-=_+1`~world1----"),
-            "hello-42349-1-world1"
-        );
+/// Serialize to YAML, the keys of the result being in a stable order, without the trailing newline added when printed.
+/// The value is serialized to JSON first, which its YAML is written from in block style, keys in the order serialized.
+fn yaml(value: &impl Serialize) -> Result<String, serde_json::Error> {
+    let node = serde_json::from_str::<YamlNode>(&serde_json::to_string(value)?)?;
+    let mut yaml = String::new();
+    match &node {
+        YamlNode::Mapping(entries) if !entries.is_empty() => {
+            write_yaml_mapping(entries, 0, &mut yaml)
+        }
+        YamlNode::Sequence(items) if !items.is_empty() => write_yaml_sequence(items, 0, &mut yaml),
+        node => yaml.push_str(&yaml_flow(node)),
     }
+    Ok(yaml.trim_end().to_string())
+}
+
+/// A JSON value keeping the order of its object keys, which serde_json's own maps sort.
+enum YamlNode {
+    Scalar(serde_json::Value),
+    Sequence(Vec<YamlNode>),
+    Mapping(Vec<(String, YamlNode)>),
+}
+
+impl<'de> Deserialize<'de> for YamlNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = YamlNode;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON value")
+            }
+
+            fn visit_unit<E>(self) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(serde_json::Value::Null))
+            }
+
+            fn visit_bool<E>(self, value: bool) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(value.into()))
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(value.into()))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(value.into()))
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(value.into()))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<YamlNode, E> {
+                Ok(YamlNode::Scalar(value.into()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<YamlNode, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(YamlNode::Sequence(items))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<YamlNode, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(YamlNode::Mapping(entries))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// Write the entries of a mapping at the given indentation, a line per key and nested collections below it.
+fn write_yaml_mapping(entries: &[(String, YamlNode)], indent: usize, yaml: &mut String) {
+    for (key, value) in entries {
+        yaml.push_str(&format!("{:indent$}{}:", "", yaml_string(key)));
+        match value {
+            YamlNode::Mapping(entries) if !entries.is_empty() => {
+                yaml.push('\n');
+                write_yaml_mapping(entries, indent + 2, yaml);
+            }
+            YamlNode::Sequence(items) if !items.is_empty() => {
+                yaml.push('\n');
+                write_yaml_sequence(items, indent, yaml);
+            }
+            value => yaml.push_str(&format!(" {}\n", yaml_flow(value))),
+        }
+    }
+}
+
+/// Write the items of a sequence at the given indentation, a "- " per item and nested mappings beside it.
+fn write_yaml_sequence(items: &[YamlNode], indent: usize, yaml: &mut String) {
+    for item in items {
+        match item {
+            YamlNode::Mapping(entries) if !entries.is_empty() => {
+                let mut mapping = String::new();
+                write_yaml_mapping(entries, indent + 2, &mut mapping);
+                yaml.push_str(&format!("{:indent$}- {}", "", &mapping[indent + 2..]));
+            }
+            YamlNode::Sequence(items) if !items.is_empty() => {
+                yaml.push_str(&format!("{:indent$}-\n", ""));
+                write_yaml_sequence(items, indent + 2, yaml);
+            }
+            item => yaml.push_str(&format!("{:indent$}- {}\n", "", yaml_flow(item))),
+        }
+    }
+}
+
+/// A scalar or empty collection on a single line.
+fn yaml_flow(node: &YamlNode) -> String {
+    match node {
+        YamlNode::Scalar(serde_json::Value::String(value)) => yaml_string(value),
+        YamlNode::Scalar(value) => value.to_string(),
+        YamlNode::Sequence(_) => "[]".to_string(),
+        YamlNode::Mapping(_) => "{}".to_string(),
+    }
+}
+
+/// A string as a plain scalar where YAML would read it back as the same string, and double-quoted otherwise.
+fn yaml_string(value: &str) -> String {
+    let lowercase = value.to_ascii_lowercase();
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(|c| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !value.contains(char::is_control)
+        && value.parse::<f64>().is_err()
+        && !lowercase.starts_with("0x")
+        && !lowercase.starts_with("0o")
+        && ![
+            "~", "null", "true", "false", "yes", "no", "on", "off", "y", "n", ".inf", ".nan",
+        ]
+        .contains(&lowercase.as_str());
+    if plain {
+        value.to_string()
+    } else {
+        // A JSON string is a valid double-quoted YAML scalar.
+        serde_json::Value::from(value).to_string()
+    }
+}
+
+/// Print a version in the --version-format template, if any, with its numeric components padded to the --pad width.
+fn format_version(version: &Version, args: &ComputeArgs) -> String {
+    let template = args
+        .version_format
+        .as_deref()
+        .unwrap_or("{major}.{minor}.{patch}{pre}{build}");
+    let width = args.pad.unwrap_or(0);
+    let padded = |component: u64| format!("{component:0width$}");
+    let prefixed = |prefix, identifiers: &str| {
+        if identifiers.is_empty() {
+            String::new()
+        } else {
+            format!("{prefix}{identifiers}")
+        }
+    };
+    template
+        .replace("{major}", &padded(version.major))
+        .replace("{minor}", &padded(version.minor))
+        .replace("{patch}", &padded(version.patch))
+        .replace("{pre}", &prefixed('-', version.pre.as_str()))
+        .replace("{build}", &prefixed('+', version.build.as_str()))
 }
@@ -0,0 +1,342 @@
+mod common;
+
+use common::TestRepo;
+use git2::Repository;
+use tempfile::TempDir;
+
+#[test]
+fn test_compute_is_default() {
+    let repo = TestRepo::ahead_of_tag();
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["compute"]), "1.2.4");
+    assert_eq!(
+        repo.version(&["compute", "--default-increment", "minor"]),
+        repo.version(&["--default-increment", "minor"])
+    );
+}
+
+#[test]
+fn test_repository_option_is_global() {
+    let repo = TestRepo::ahead_of_tag();
+    let elsewhere = TempDir::new().unwrap();
+    let path = repo.path().to_str().unwrap();
+    let output = repo
+        .command(&["compute", "-C", path])
+        .current_dir(elsewhere.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+}
+
+#[test]
+fn test_tag() {
+    let repo = TestRepo::ahead_of_tag();
+    assert_eq!(repo.version(&["tag"]), "1.2.4");
+    let tagged = repo
+        .repository
+        .find_reference("refs/tags/1.2.4")
+        .unwrap()
+        .target()
+        .unwrap();
+    assert_eq!(tagged, repo.head());
+    assert!(repo
+        .error(&["tag"])
+        .contains("HEAD already tagged with semver"));
+}
+
+#[test]
+fn test_tag_push() {
+    let repo = TestRepo::ahead_of_tag();
+    let remote = TempDir::new().unwrap();
+    let bare = Repository::init_bare(remote.path()).unwrap();
+    repo.repository
+        .remote("origin", remote.path().to_str().unwrap())
+        .unwrap();
+    assert_eq!(repo.version(&["tag", "--push", "origin"]), "1.2.4");
+    let pushed = bare
+        .find_reference("refs/tags/1.2.4")
+        .unwrap()
+        .target()
+        .unwrap();
+    assert_eq!(pushed, repo.head());
+}
+
+#[test]
+fn test_tag_dry_run() {
+    let repo = TestRepo::ahead_of_tag();
+    let tags = |repo: &TestRepo| repo.repository.tag_names(None).unwrap().len();
+
+    let output = repo.run(&["tag", "--dry-run", "--push", "origin"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "would create lightweight tag 1.2.4 on commit {}\nwould push tag 1.2.4 to remote origin\n",
+            repo.head()
+        )
+    );
+    assert_eq!(tags(&repo), 1);
+
+    let output = repo.run(&["tag", "--dry-run", "--annotate"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "would create annotated tag 1.2.4 on commit {}\n",
+            repo.head()
+        )
+    );
+    assert_eq!(tags(&repo), 1);
+    assert!(repo.repository.find_reference("refs/tags/1.2.4").is_err());
+}
+
+#[test]
+fn test_validate() {
+    let repo = TestRepo::ahead_of_tag();
+    assert_eq!(repo.version(&["validate", "1.2.4"]), "");
+    let output = repo.run(&["validate", "1.3.0"]);
+    assert_eq!(output.status.code(), Some(7));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("1.2.4"));
+    assert_eq!(repo.version(&["validate", "1.3.0", "-i", "minor"]), "");
+}
+
+#[test]
+fn test_list() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    let second = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0", second);
+    repo.tag_lightweight("not-semver", second);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["list"]), "1.0.0\n1.1.0");
+}
+
+#[test]
+fn test_list_matches_descendant_check() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    repo.checkout_new_branch("minor/thing", first);
+    let side = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0-thing.1", side);
+    repo.checkout_new_branch("other", first);
+    let other = repo.commit("Add other thing");
+    repo.tag_lightweight("9.0.0", other);
+    repo.checkout_branch("main");
+    let second = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.1", second);
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    let head = repo.commit("Fix another typo");
+
+    // Check each tag for reachability on its own, the way a single walk from HEAD should agree with.
+    let mut expected = repo
+        .repository
+        .tag_names(None)
+        .unwrap()
+        .iter()
+        .flatten()
+        .filter_map(|name| {
+            let version = semver::Version::parse(name).ok()?;
+            let commit = repo
+                .repository
+                .revparse_single(name)
+                .unwrap()
+                .peel_to_commit()
+                .unwrap()
+                .id();
+            let reachable =
+                commit == head || repo.repository.graph_descendant_of(head, commit).unwrap();
+            reachable.then_some(version)
+        })
+        .collect::<Vec<_>>();
+    expected.sort();
+    let expected = expected
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(repo.version(&["list"]), expected);
+    assert_eq!(expected, "1.0.0\n1.0.1\n1.1.0-thing.1");
+}
+
+#[test]
+fn test_global_options_before_subcommand() {
+    let repo = TestRepo::ahead_of_tag();
+    let elsewhere = TempDir::new().unwrap();
+    let path = repo.path().to_str().unwrap();
+    let output = repo
+        .command(&["-C", path, "--quiet", "list"])
+        .current_dir(elsewhere.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.3\n");
+}
+
+#[test]
+fn test_compute_options_before_subcommand() {
+    let repo = TestRepo::ahead_of_tag();
+    let output = repo.run(&["--main-branch", "trunk", "list"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("the subcommand 'list' cannot be used with '--main-branch'"));
+}
+
+#[test]
+fn test_tag_annotated() {
+    let repo = TestRepo::ahead_of_tag();
+    let error = repo.error(&["tag", "--annotate"]);
+    assert!(error.contains("configure user.name and user.email or create a lightweight tag"));
+    assert!(repo.repository.find_reference("refs/tags/1.2.4").is_err());
+
+    let mut config = repo.repository.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    assert_eq!(
+        repo.version(&["tag", "--message", "Release 1.2.4"]),
+        "1.2.4"
+    );
+    let tag = repo
+        .repository
+        .find_reference("refs/tags/1.2.4")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+    assert_eq!(tag.message(), Some("Release 1.2.4"));
+    assert_eq!(tag.target_id(), repo.head());
+    assert_eq!(tag.tagger().unwrap().name(), Some("Test"));
+}
+
+#[test]
+fn test_from_to() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    let second = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.1", second);
+    repo.checkout_new_branch("minor/thing", second);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let merge = repo.merge("minor/thing", "Merge branch minor/thing");
+
+    // From an actual tag, the same as the tag based computation.
+    assert_eq!(repo.version(&[]), "1.1.0");
+    assert_eq!(repo.version(&["--from", "1.0.1"]), "1.1.0");
+    // Disregarding the tags in between.
+    assert_eq!(repo.version(&["--from", "1.0.0"]), "1.1.0");
+    assert_eq!(
+        repo.version(&["--from", "1.0.0", "--to", "1.0.1", "--show", "increment"]),
+        "patch"
+    );
+
+    // From an untagged commit, as if tagged with the version computed for it, 1.0.2 from the base 1.0.1.
+    let third = repo.commit("Fix another typo");
+    let head = repo.commit("Fix yet another typo");
+    assert_eq!(repo.version(&["--from", &third.to_string()]), "1.0.3");
+    assert_eq!(
+        repo.version(&["--from", "1.0.0", "--to", &merge.to_string()]),
+        "1.1.0"
+    );
+
+    let output = repo.run(&["--from", &head.to_string(), "--to", "1.0.0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(repo.run(&["--from", "HEAD"]).status.code(), Some(4));
+}
+
+#[test]
+fn test_tag_require_clean() {
+    let repo = TestRepo::new();
+    repo.stage("README.md", "Hello");
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.2.3", tagged);
+    repo.commit("Fix typo");
+
+    std::fs::write(repo.path().join("untracked.txt"), "Ignored").unwrap();
+    std::fs::write(repo.path().join("README.md"), "Hello, world").unwrap();
+    let output = repo.run(&["tag", "--require-clean"]);
+    assert_eq!(output.status.code(), Some(12));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: Error(\"working tree has uncommitted changes to README.md, commit or stash them before tagging\")\n"
+    );
+    assert!(repo.repository.find_reference("refs/tags/1.2.4").is_err());
+
+    repo.stage("README.md", "Hello, world");
+    assert_eq!(
+        repo.run(&["tag", "--require-clean"]).status.code(),
+        Some(12)
+    );
+
+    repo.commit("Fix another typo");
+    assert_eq!(repo.version(&["tag", "--require-clean"]), "1.2.4");
+    assert!(repo.repository.find_reference("refs/tags/1.2.4").is_ok());
+}
+
+#[test]
+fn test_tag_message_changelog() {
+    let repo = TestRepo::ahead_of_tag();
+    let base = repo.head();
+    repo.checkout_new_branch("minor/thing", base);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let minor = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    let mut config = repo.repository.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    let message_file = repo.path().join(".git").join("MESSAGE");
+    std::fs::write(&message_file, "Release notes\n\n{changelog}\n").unwrap();
+    assert_eq!(
+        repo.version(&[
+            "tag",
+            "--message-file",
+            message_file.to_str().unwrap(),
+            "--tag-template",
+            "v{version}"
+        ]),
+        "0.1.0"
+    );
+    let tag = repo
+        .repository
+        .find_reference("refs/tags/v0.1.0")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+    assert_eq!(
+        tag.message(),
+        Some(
+            format!(
+                "Release notes\n\nminor:\n- {} Merge branch 'minor/thing'\n\nnone:\n- {} Fix typo\n- {} Initial commit\n",
+                repo.short_id(minor),
+                repo.short_id(base),
+                repo.short_id(repo.repository.find_commit(base).unwrap().parent_id(0).unwrap()),
+            )
+            .as_str()
+        )
+    );
+
+    assert_eq!(repo.version(&["tag", "--message", "{changelog}"]), "1.3.0");
+    let tag = repo
+        .repository
+        .find_reference("refs/tags/1.3.0")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+    assert_eq!(
+        tag.message(),
+        Some(
+            format!(
+                "minor:\n- {} Merge branch 'minor/thing'\n\nnone:\n- {} Fix typo",
+                repo.short_id(minor),
+                repo.short_id(base)
+            )
+            .as_str()
+        )
+    );
+}
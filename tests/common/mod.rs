@@ -0,0 +1,234 @@
+#![allow(dead_code)]
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+use tempfile::TempDir;
+
+pub struct TestRepo {
+    pub dir: TempDir,
+    pub home: TempDir,
+    pub repository: Repository,
+}
+
+impl TestRepo {
+    /// Initialize an empty repository whose initial branch is "main".
+    pub fn new() -> Self {
+        Self::with_initial_branch("main")
+    }
+
+    pub fn with_initial_branch(branch: &str) -> Self {
+        Self::init(TempDir::new().unwrap(), branch)
+    }
+
+    /// Initialize an empty repository whose initial branch is "main" in a new directory within the given one, eg. to
+    /// nest it in another repository's working tree.
+    pub fn new_in(parent: &Path) -> Self {
+        Self::init(TempDir::new_in(parent).unwrap(), "main")
+    }
+
+    fn init(dir: TempDir, branch: &str) -> Self {
+        let home = TempDir::new().unwrap();
+        let repository = Repository::init_opts(
+            dir.path(),
+            RepositoryInitOptions::new().initial_head(branch),
+        )
+        .unwrap();
+        Self {
+            dir,
+            home,
+            repository,
+        }
+    }
+
+    /// Initialize a repository whose only commit, HEAD, is tagged "1.2.3".
+    pub fn tagged() -> Self {
+        let repo = Self::new();
+        let tagged = repo.commit("Initial commit");
+        repo.tag_lightweight("1.2.3", tagged);
+        repo
+    }
+
+    /// Initialize a repository whose HEAD is a commit on top of one tagged "1.2.3", so that it computes "1.2.4".
+    pub fn ahead_of_tag() -> Self {
+        let repo = Self::tagged();
+        repo.commit("Fix typo");
+        repo
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn signature() -> Signature<'static> {
+        Signature::now("Test", "test@example.com").unwrap()
+    }
+
+    /// Commit the current index on top of HEAD with the given message.
+    pub fn commit(&self, message: &str) -> Oid {
+        let parents = self
+            .repository
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok())
+            .into_iter()
+            .collect::<Vec<_>>();
+        self.commit_with_parents(message, &parents.iter().collect::<Vec<_>>())
+    }
+
+    /// Commit the current index on top of HEAD with the given message, authored by someone other than the committer.
+    pub fn commit_by(&self, message: &str, name: &str, email: &str) -> Oid {
+        let author = Signature::now(name, email).unwrap();
+        let parent = self.repository.head().unwrap().peel_to_commit().unwrap();
+        let tree_id = self.repository.index().unwrap().write_tree().unwrap();
+        let tree = self.repository.find_tree(tree_id).unwrap();
+        self.repository
+            .commit(
+                Some("HEAD"),
+                &author,
+                &Self::signature(),
+                message,
+                &tree,
+                &[&parent],
+            )
+            .unwrap()
+    }
+
+    /// Create a merge commit on HEAD whose second parent is the tip of the given branch.
+    pub fn merge(&self, branch: &str, message: &str) -> Oid {
+        let head = self.repository.head().unwrap().peel_to_commit().unwrap();
+        let other = self
+            .repository
+            .find_branch(branch, git2::BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        self.commit_with_parents(message, &[&head, &other])
+    }
+
+    pub fn commit_with_parents(&self, message: &str, parents: &[&git2::Commit]) -> Oid {
+        let signature = Self::signature();
+        let tree_id = self.repository.index().unwrap().write_tree().unwrap();
+        let tree = self.repository.find_tree(tree_id).unwrap();
+        self.repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                parents,
+            )
+            .unwrap()
+    }
+
+    /// Write a file into the working tree and stage it.
+    pub fn stage(&self, path: &str, contents: &str) {
+        let full_path = self.path().join(path);
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(full_path, contents).unwrap();
+        let mut index = self.repository.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+    }
+
+    pub fn tag_lightweight(&self, name: &str, target: Oid) {
+        let object = self.repository.find_object(target, None).unwrap();
+        self.repository
+            .tag_lightweight(name, &object, false)
+            .unwrap();
+    }
+
+    pub fn tag_annotated(&self, name: &str, target: Oid) {
+        let object = self.repository.find_object(target, None).unwrap();
+        self.repository
+            .tag(name, &object, &Self::signature(), name, false)
+            .unwrap();
+    }
+
+    /// Create a branch at the given commit and point HEAD at it.
+    pub fn checkout_new_branch(&self, name: &str, target: Oid) {
+        let commit = self.repository.find_commit(target).unwrap();
+        self.repository.branch(name, &commit, false).unwrap();
+        self.checkout_branch(name);
+    }
+
+    pub fn checkout_branch(&self, name: &str) {
+        self.repository
+            .set_head(&format!("refs/heads/{name}"))
+            .unwrap();
+    }
+
+    pub fn checkout_detached(&self, target: Oid) {
+        self.repository.set_head_detached(target).unwrap();
+    }
+
+    /// Point a branch at the given commit, creating it if need be, without touching HEAD.
+    pub fn reset_branch(&self, name: &str, target: Oid) {
+        let commit = self.repository.find_commit(target).unwrap();
+        self.repository.branch(name, &commit, true).unwrap();
+    }
+
+    pub fn head(&self) -> Oid {
+        self.repository.head().unwrap().target().unwrap()
+    }
+
+    pub fn short_id(&self, target: Oid) -> String {
+        self.repository
+            .find_object(target, None)
+            .unwrap()
+            .short_id()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Run the git-semver binary from the root of the repository.
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_git-semver"));
+        command
+            .args(args)
+            .current_dir(self.path())
+            .env("HOME", self.home.path())
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE");
+        command
+    }
+
+    /// Run the git-semver binary, asserting success, and return its trimmed stdout.
+    pub fn version(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "git-semver {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Run the git-semver binary, asserting failure, and return its stderr.
+    pub fn error(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            !output.status.success(),
+            "git-semver {args:?} unexpectedly succeeded: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        String::from_utf8(output.stderr).unwrap()
+    }
+}
@@ -0,0 +1,51 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_exec_substitutes_version() {
+    let repo = TestRepo::ahead_of_tag();
+    assert_eq!(
+        repo.version(&["--exec", "printf '%s %s' {version} {tag} > version.txt"]),
+        "1.2.4"
+    );
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("version.txt")).unwrap(),
+        "1.2.4 1.2.4"
+    );
+}
+
+#[test]
+fn test_exec_forwards_exit_code() {
+    let repo = TestRepo::ahead_of_tag();
+    let output = repo.run(&["--exec", "exit 3"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+}
+
+#[test]
+fn test_resolver_cmd() {
+    let repo = TestRepo::ahead_of_tag();
+    let resolver = [
+        "--resolver-cmd",
+        r#"test "$1" = "$(git rev-parse HEAD)" || exit 1; case "$2" in Break*) echo major ;; Add*) echo Feature ;; Skip*) echo none ;; Odd*) echo sideways ;; *) exit 1 ;; esac"#,
+    ];
+    assert_eq!(repo.version(&resolver), "1.2.4");
+
+    for (summary, version) in [
+        ("Break everything", "2.0.0"),
+        ("Add thing", "1.3.0"),
+        ("Skip this", "1.2.3"),
+        ("Odd one out", "1.2.4"),
+    ] {
+        repo.commit(summary);
+        assert_eq!(repo.version(&resolver), version);
+    }
+    repo.commit("Skip another");
+    assert_eq!(
+        repo.version(&[&resolver[..], &["--increment", "minor"]].concat()),
+        "1.3.0"
+    );
+}
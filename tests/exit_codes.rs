@@ -0,0 +1,123 @@
+mod common;
+
+use common::TestRepo;
+use tempfile::TempDir;
+
+fn exit_code(repo: &TestRepo, args: &[&str]) -> Option<i32> {
+    repo.run(args).status.code()
+}
+
+#[test]
+fn test_success() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    assert_eq!(exit_code(&repo, &[]), Some(0));
+}
+
+#[test]
+fn test_usage_error() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    assert_eq!(exit_code(&repo, &["--no-such-flag"]), Some(2));
+    assert_eq!(exit_code(&repo, &["--match-expression", "("]), Some(2));
+    assert_eq!(
+        exit_code(&repo, &["--match-expression", "^Merge .*/[\\w-]+"]),
+        Some(2)
+    );
+    assert!(repo
+        .error(&["--match-expression", "^Merge"])
+        .contains("match expression \"^Merge\" has no capture group"));
+}
+
+#[test]
+fn test_repository_not_found() {
+    let repo = TestRepo::new();
+    let elsewhere = TempDir::new().unwrap();
+    let output = repo
+        .command(&[])
+        .current_dir(elsewhere.path())
+        .env(
+            "GIT_CEILING_DIRECTORIES",
+            elsewhere.path().parent().unwrap(),
+        )
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_head_with_semver_tag() {
+    let repo = TestRepo::new();
+    let head = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", head);
+    assert_eq!(exit_code(&repo, &[]), Some(4));
+}
+
+#[test]
+fn test_commit_summary_without_increment_level() {
+    let repo = TestRepo::new();
+    let base = repo.commit("Initial commit");
+    repo.checkout_new_branch("feature", base);
+    repo.commit("Add feature");
+    repo.checkout_branch("main");
+    repo.merge("feature", "Merge branch 'feature'");
+    assert_eq!(exit_code(&repo, &[]), Some(5));
+}
+
+#[test]
+fn test_invalid_prerelease() {
+    let repo = TestRepo::new();
+    let base = repo.commit("Initial commit");
+    repo.checkout_new_branch("feature", base);
+    repo.commit("Add thing");
+    assert_eq!(exit_code(&repo, &["--prerelease-id", "///"]), Some(6));
+    assert_eq!(
+        exit_code(&repo, &["--prerelease-revision", "rc.01"]),
+        Some(6)
+    );
+    assert_eq!(
+        exit_code(
+            &repo,
+            &["--prerelease-revision", "rc.01", "--trim-leading-zeros"]
+        ),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_check_head_with_latest_tag() {
+    let repo = TestRepo::new();
+    let older = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", older);
+    let head = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.1", head);
+    let output = repo.run(&["--check"]);
+    assert_eq!(output.status.code(), Some(10));
+    assert!(output.stdout.is_empty());
+    assert_eq!(exit_code(&repo, &[]), Some(4));
+}
+
+#[test]
+fn test_check_head_with_older_tag() {
+    let repo = TestRepo::new();
+    let newer = repo.commit("Initial commit");
+    repo.tag_lightweight("2.0.0", newer);
+    let head = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.0", head);
+    assert_eq!(exit_code(&repo, &["--check"]), Some(4));
+}
+
+#[test]
+fn test_check_untagged_head() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["--check"]), "1.0.1");
+}
+
+#[test]
+fn test_unborn_branch() {
+    let repo = TestRepo::new();
+    assert_eq!(exit_code(&repo, &[]), Some(13));
+}
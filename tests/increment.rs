@@ -0,0 +1,523 @@
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_increment_from_env_json() {
+    let repo = TestRepo::tagged();
+    repo.commit("Add thing");
+    let output = repo
+        .command(&["--increment-from-env", "PR_LABELS"])
+        .env("PR_LABELS", r#"["enhancement","semver:minor"]"#)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.3.0\n");
+}
+
+#[test]
+fn test_increment_from_env_comma_list() {
+    let repo = TestRepo::tagged();
+    repo.commit("Remove thing");
+    let output = repo
+        .command(&["--increment-from-env", "PR_LABELS"])
+        .env("PR_LABELS", "breaking,semver:major")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2.0.0\n");
+}
+
+#[test]
+fn test_increment_from_env_without_label() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    let output = repo
+        .command(&["--increment-from-env", "PR_LABELS"])
+        .env("PR_LABELS", "documentation")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+    assert_eq!(
+        repo.version(&["--increment-from-env", "PR_LABELS"]),
+        "1.2.4"
+    );
+}
+
+#[test]
+fn test_increment_precedes_increment_from_env() {
+    let repo = TestRepo::tagged();
+    repo.commit("Add thing");
+    let output = repo
+        .command(&["--increment-from-env", "PR_LABELS", "--increment", "patch"])
+        .env("PR_LABELS", "semver:major")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.2.4\n");
+}
+
+#[test]
+fn test_bump_from_title_env() {
+    let repo = TestRepo::tagged();
+    let base = repo.commit("Prepare");
+    repo.checkout_new_branch("minor/thing", base);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    let run = |title: &str, args: &[&str]| {
+        let output = repo
+            .command(
+                &[
+                    &["--bump-from-title-env", "PR_TITLE"],
+                    args,
+                    &["--match-expression", r"^(feat|fix)(\(.+\))?:"],
+                ]
+                .concat(),
+            )
+            .env("PR_TITLE", title)
+            .env("PR_LABELS", "semver:major")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    // The title takes precedence over the merge commit's own summary, which the expression does not even match.
+    assert_eq!(run("feat: Add thing", &[]), "1.3.0\n");
+    assert_eq!(run("fix(parser): Fix typo", &[]), "1.2.4\n");
+    assert_eq!(run("feat: Add thing", &["--increment", "patch"]), "1.2.4\n");
+    assert_eq!(
+        run("feat: Add thing", &["--increment-from-env", "PR_LABELS"]),
+        "2.0.0\n"
+    );
+    assert!(repo
+        .error(&[
+            "--bump-from-title-env",
+            "PR_TITLE",
+            "--match-expression",
+            "^(feat):"
+        ])
+        .contains("cannot derive version increment level from commit summary"));
+
+    repo.commit("Fix typo");
+    assert_eq!(run("Update docs", &[]), "1.2.4\n");
+}
+
+#[test]
+fn test_default_increment_none() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["--default-increment", "none"]), "1.2.3");
+    assert_eq!(
+        repo.version(&["--default-increment", "none", "--show", "increment"]),
+        "none"
+    );
+}
+
+#[test]
+fn test_default_increment_none_still_bumps_matched_merges() {
+    let repo = TestRepo::tagged();
+    let main = repo.head();
+    repo.checkout_new_branch("minor/thing", main);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch minor/thing");
+    assert_eq!(repo.version(&["--default-increment", "none"]), "1.3.0");
+}
+
+#[test]
+fn test_trailer() {
+    let repo = TestRepo::tagged();
+    repo.commit("Add thing\n\nBump: minor\n");
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["--trailer", "Bump"]), "1.3.0");
+    assert_eq!(repo.version(&["--trailer", "Release"]), "1.2.4");
+}
+
+#[test]
+fn test_trailer_precedes_match_expression() {
+    let repo = TestRepo::tagged();
+    let main = repo.head();
+    repo.checkout_new_branch("minor/thing", main);
+    repo.commit("Remove thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch minor/thing\n\nBump: major\n");
+    assert_eq!(repo.version(&[]), "1.3.0");
+    assert_eq!(repo.version(&["--trailer", "Bump"]), "2.0.0");
+    assert_eq!(
+        repo.version(&["--trailer", "Bump", "--increment", "patch"]),
+        "1.2.4"
+    );
+}
+
+#[test]
+fn test_increment_on_tagged_head() {
+    let repo = TestRepo::tagged();
+    for args in [
+        &[][..],
+        &["--increment", "major"],
+        &["--default-increment", "none"],
+    ] {
+        let output = repo.run(args);
+        assert_eq!(output.status.code(), Some(4));
+    }
+}
+
+#[test]
+fn test_increment_on_single_untagged_commit() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    assert_eq!(repo.version(&[]), "0.0.1");
+    assert_eq!(repo.version(&["--increment", "minor"]), "0.1.0");
+    assert_eq!(repo.version(&["--increment", "major"]), "1.0.0");
+    assert_eq!(repo.version(&["--default-increment", "none"]), "0.0.0");
+}
+
+#[test]
+fn test_no_implicit_initial_bump() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    let flag = "--no-implicit-initial-bump";
+    assert_eq!(repo.version(&[]), "0.0.1");
+    assert_eq!(repo.version(&[flag]), "0.0.0");
+    assert_eq!(repo.version(&[flag, "--increment", "minor"]), "0.1.0");
+    assert_eq!(repo.version(&[flag, "--min-increment", "patch"]), "0.0.1");
+    assert_eq!(
+        repo.version(&[flag, "--staged", "--summary", "Merge minor/thing"]),
+        "0.0.0"
+    );
+
+    repo.checkout_new_branch("minor/thing", first);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    assert_eq!(repo.version(&[]), "0.1.0");
+    assert_eq!(repo.version(&[flag]), "0.0.0");
+
+    repo.tag_lightweight("0.0.0", first);
+    assert_eq!(repo.version(&[flag]), "0.1.0");
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[flag]), "0.0.1");
+}
+
+#[test]
+fn test_no_default_head_bump() {
+    let repo = TestRepo::tagged();
+    let main = repo.head();
+    repo.checkout_new_branch("feature", main);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("feature", "Merge branch feature");
+    assert_eq!(repo.run(&[]).status.code(), Some(5));
+    assert_eq!(repo.version(&["--no-default-head-bump"]), "1.2.3");
+
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["--no-default-head-bump"]), "1.2.3");
+    assert_eq!(
+        repo.version(&["--no-default-head-bump", "--increment", "minor"]),
+        "1.3.0"
+    );
+}
+
+#[test]
+fn test_max_version() {
+    let repo = TestRepo::tagged();
+    repo.commit("Remove thing");
+    let args = ["--increment", "major", "--max-version", "1.999.999"];
+    let output = repo.run(&args);
+    assert_eq!(output.status.code(), Some(9));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("version 2.0.0 exceeds the maximum version 1.999.999"));
+    assert_eq!(
+        repo.version(&[&args[..], &["--on-ceiling", "clamp"]].concat()),
+        "1.999.999"
+    );
+    assert_eq!(
+        repo.version(&["--increment", "minor", "--max-version", "1.999.999"]),
+        "1.3.0"
+    );
+    assert_eq!(repo.version(&["--max-version", "1.2.4"]), "1.2.4");
+}
+
+#[test]
+fn test_highest_match() {
+    let repo = TestRepo::tagged();
+    let main = repo.head();
+    repo.checkout_new_branch("feature", main);
+    repo.commit("Add and remove things");
+    repo.checkout_branch("main");
+    repo.merge("feature", "Merge minor/thing and major/other");
+    let expression = r"(patch|minor|major)/[\w-]+";
+    assert_eq!(repo.version(&["-e", expression]), "1.3.0");
+    assert_eq!(
+        repo.version(&["-e", expression, "--highest-match"]),
+        "2.0.0"
+    );
+}
+
+#[test]
+fn test_staged_summary() {
+    let repo = TestRepo::tagged();
+    assert_eq!(
+        repo.version(&["--staged", "--summary", "Merge minor/thing"]),
+        "1.3.0"
+    );
+    repo.commit("Add thing");
+    assert_eq!(
+        repo.version(&["--staged", "--summary", "minor/other", "-e", r"(minor)/"]),
+        "1.3.0"
+    );
+    assert_eq!(
+        repo.version(&["--staged", "--summary", "Add other"]),
+        "1.2.4"
+    );
+    assert_eq!(
+        repo.version(&[
+            "--staged",
+            "--summary",
+            "Add other",
+            "--default-increment",
+            "none"
+        ]),
+        "1.2.3"
+    );
+    assert!(!repo.run(&["--staged"]).status.success());
+}
+
+#[test]
+fn test_on_empty_summary() {
+    let repo = TestRepo::tagged();
+    repo.commit("");
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["--on-empty-summary", "default"]), "1.2.4");
+    assert_eq!(repo.version(&["--on-empty-summary", "skip"]), "1.2.3");
+    assert_eq!(
+        repo.version(&["--on-empty-summary", "skip", "--increment", "minor"]),
+        "1.3.0"
+    );
+    let output = repo.run(&["--on-empty-summary", "error"]);
+    assert_eq!(output.status.code(), Some(5));
+
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["--on-empty-summary", "error"]), "1.2.4");
+    assert_eq!(
+        repo.version(&["--staged", "--summary", "", "--on-empty-summary", "skip"]),
+        "1.2.3"
+    );
+}
+
+#[test]
+fn test_next_on_tagged_head() {
+    let repo = TestRepo::tagged();
+    assert_eq!(repo.run(&[]).status.code(), Some(4));
+    assert_eq!(repo.version(&["--next"]), "1.2.4");
+    assert_eq!(repo.version(&["--next", "--increment", "minor"]), "1.3.0");
+    assert_eq!(
+        repo.version(&["--next", "--show", "base-commit"]),
+        repo.head().to_string()
+    );
+
+    repo.checkout_new_branch("minor/thing", repo.head());
+    let side = repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let merge = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    repo.tag_lightweight("1.3.0", merge);
+    assert_eq!(repo.version(&["--next"]), "1.4.0");
+
+    repo.checkout_new_branch("feature", side);
+    let head = repo.commit("Add other thing");
+    assert_eq!(
+        repo.version(&["--next"]),
+        format!("1.2.3-feature.{}", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_branch_prefix_map() {
+    let map = [
+        "--branch-prefix-map",
+        "feature=minor,bugfix=patch,hotfix=patch,breaking=major",
+    ];
+    for (branch, version) in [
+        ("feature/thing", "1.3.0"),
+        ("bugfix/typo", "1.2.4"),
+        ("hotfix/crash", "1.2.4"),
+        ("breaking/api", "2.0.0"),
+    ] {
+        let repo = TestRepo::tagged();
+        repo.checkout_new_branch(branch, repo.head());
+        repo.commit("Change thing");
+        repo.checkout_branch("main");
+        repo.merge(branch, &format!("Merge branch '{branch}'"));
+        assert_eq!(repo.run(&[]).status.code(), Some(5), "{branch}");
+        assert_eq!(repo.version(&map), version, "{branch}");
+    }
+
+    let repo = TestRepo::tagged();
+    repo.checkout_new_branch("minor/thing", repo.head());
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    assert_eq!(repo.version(&map), "1.3.0");
+    assert_eq!(
+        repo.version(&["--branch-prefix-map", "minor=major"]),
+        "2.0.0"
+    );
+    assert_eq!(
+        repo.version(&[
+            "--staged",
+            "--summary",
+            "Merge branch 'breaking/api'",
+            "--branch-prefix-map",
+            "breaking=major"
+        ]),
+        "2.0.0"
+    );
+    assert_eq!(
+        repo.run(&["--branch-prefix-map", "feature"]).status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_min_increment() {
+    let repo = TestRepo::tagged();
+    let main = repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["--min-increment", "minor"]), "1.3.0");
+    assert_eq!(
+        repo.version(&["--min-increment", "minor", "--show", "increment"]),
+        "minor"
+    );
+    assert_eq!(
+        repo.version(&["--min-increment", "minor", "--increment", "patch"]),
+        "1.3.0"
+    );
+    assert_eq!(
+        repo.version(&["--min-increment", "minor", "--increment", "major"]),
+        "2.0.0"
+    );
+    assert_eq!(
+        repo.version(&["--min-increment", "feat", "--default-increment", "none"]),
+        "1.3.0"
+    );
+    assert_eq!(
+        repo.version(&["--min-increment", "patch", "--no-default-head-bump"]),
+        "1.2.4"
+    );
+
+    // Prereleases off the main branch are left alone.
+    repo.checkout_new_branch("feature", main);
+    let head = repo.commit("Add thing");
+    assert_eq!(
+        repo.version(&["--min-increment", "minor"]),
+        format!("1.2.3-feature.{}", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_version_overflow() {
+    let max = u64::MAX;
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight(&format!("1.{max}.{max}"), tagged);
+    repo.commit("Fix typo");
+
+    let output = repo.run(&[]);
+    assert_eq!(output.status.code(), Some(9));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Error: Error(\"version 1.{max}.{max} cannot be incremented by patch, its patch component is at the maximum\")\n")
+    );
+    assert_eq!(repo.run(&["-i", "minor"]).status.code(), Some(9));
+    assert_eq!(repo.version(&["-i", "major"]), "2.0.0");
+    assert_eq!(repo.run(&["--preview-all"]).status.code(), Some(9));
+
+    // As many increments as there are, each starting from the tagged version, none may wrap around.
+    for (tag, level) in [
+        (format!("{max}.0.0"), "major"),
+        (format!("0.{max}.0"), "minor"),
+        (format!("0.0.{max}"), "patch"),
+    ] {
+        let repo = TestRepo::new();
+        let tagged = repo.commit("Initial commit");
+        repo.tag_lightweight(&tag, tagged);
+        repo.commit("Fix typo");
+        assert!(repo
+            .error(&["-i", level])
+            .contains(&format!("its {level} component is at the maximum")));
+    }
+}
+
+#[test]
+fn test_bump_file() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.2.3", tagged);
+    let bump_file = ["--bump-file", ".version-bump"];
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&bump_file), "1.2.4");
+
+    repo.stage(".version-bump", "minor\n");
+    repo.commit("Add thing");
+    assert_eq!(repo.version(&bump_file), "1.3.0");
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(
+        repo.version(&[&bump_file[..], &["--increment", "major"]].concat()),
+        "2.0.0"
+    );
+
+    // Once released, the unchanged file declares nothing for the next release.
+    repo.tag_lightweight("1.3.0", repo.head());
+    repo.commit("Fix another typo");
+    assert_eq!(repo.version(&bump_file), "1.3.1");
+
+    repo.stage(".version-bump", "Breaking");
+    repo.commit("Change everything");
+    assert_eq!(repo.version(&bump_file), "2.0.0");
+    repo.stage(".version-bump", "none");
+    repo.commit("Change nothing");
+    assert_eq!(repo.version(&bump_file), "1.3.0");
+
+    repo.stage(".version-bump", "sideways");
+    repo.commit("Change something");
+    let output = repo.run(&bump_file);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: Error(\"bump file .version-bump holds neither an increment level nor \"none\"\")\n"
+    );
+}
+
+#[test]
+fn test_increment_policy_highest() {
+    let repo = TestRepo::tagged();
+    for branch in [
+        "minor/thing",
+        "patch/typo",
+        "patch/other-typo",
+        "patch/docs",
+        "patch/lint",
+        "patch/deps",
+    ] {
+        repo.checkout_new_branch(branch, repo.head());
+        repo.commit("Change things");
+        repo.checkout_branch("main");
+        repo.merge(branch, &format!("Merge branch '{branch}'"));
+    }
+
+    assert_eq!(repo.version(&[]), "1.2.4");
+    assert_eq!(repo.version(&["--increment-policy", "head"]), "1.2.4");
+    assert_eq!(repo.version(&["--increment-policy", "highest"]), "1.3.0");
+    repo.commit("Tidy up");
+    assert_eq!(repo.version(&["--increment-policy", "highest"]), "1.3.0");
+    // With no level named since the base, HEAD decides as usual.
+    assert_eq!(
+        repo.version(&["--increment-policy", "highest", "--only-author", "nobody"]),
+        "1.2.4"
+    );
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_branch_tip() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let head = repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.0.1");
+
+    repo.checkout_detached(head);
+    assert_eq!(repo.version(&[]), "1.0.1");
+
+    repo.checkout_new_branch("feature", head);
+    assert_eq!(repo.version(&[]), "1.0.1");
+}
+
+#[test]
+fn test_ancestor_of_branch_tip() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let head = repo.commit("Fix typo");
+    repo.commit("Fix another typo");
+    repo.checkout_detached(head);
+    assert_eq!(repo.version(&[]), "1.0.1");
+}
+
+#[test]
+fn test_diverged_main_branch() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let abandoned = repo.commit("Add thing");
+    repo.checkout_new_branch("feature", abandoned);
+    let head = repo.commit("Add more of the thing");
+
+    // Force main onto a history which no longer contains the feature branch's base.
+    repo.checkout_detached(tagged);
+    let rewritten = repo.commit("Add thing differently");
+    repo.reset_branch("main", rewritten);
+
+    repo.checkout_branch("feature");
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.0.0-feature.{}", repo.short_id(head))
+    );
+    repo.checkout_detached(abandoned);
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.0.0-HEAD.{}", repo.short_id(abandoned))
+    );
+}
+
+#[test]
+fn test_missing_main_branch() {
+    let repo = TestRepo::with_initial_branch("trunk");
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let head = repo.commit("Fix typo");
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.0.0-trunk.{}", repo.short_id(head))
+    );
+    assert_eq!(repo.version(&["--main-branch", "trunk"]), "1.0.1");
+}
+
+#[test]
+fn test_second_parent_ancestor_of_branch_tip() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.checkout_new_branch("patch/typo", tagged);
+    let head = repo.commit("Fix typo");
+    repo.checkout_branch("main");
+    let main = repo.commit("Fix another typo");
+    repo.merge("patch/typo", "Merge branch patch/typo");
+    repo.checkout_detached(head);
+    assert_eq!(repo.version(&[]), "1.0.1");
+
+    // The outcome does not depend on the order of the merge commit's parents.
+    let (head_commit, main_commit) = (
+        repo.repository.find_commit(head).unwrap(),
+        repo.repository.find_commit(main).unwrap(),
+    );
+    let reversed = repo.commit_with_parents("Merge branch main", &[&head_commit, &main_commit]);
+    repo.reset_branch("main", reversed);
+    repo.checkout_detached(head);
+    assert_eq!(repo.version(&[]), "1.0.1");
+}
+
+#[test]
+fn test_main_branch_candidates() {
+    let repo = TestRepo::with_initial_branch("master");
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let head = repo.commit("Fix typo");
+    let args = ["--main-branch", "main", "--main-branch", "master"];
+    let output = repo.run(&args);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.0.1\n");
+    assert!(output.stderr.is_empty());
+
+    // Mid-migration, main has been created but master has moved on.
+    repo.reset_branch("main", tagged);
+    let output = repo.run(&args);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("1.0.0-master.{}\n", repo.short_id(head))
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Warning: several main branch candidates exist, main, master, using main\n"
+    );
+    assert!(repo
+        .run(&[&args[..], &["--quiet"]].concat())
+        .stderr
+        .is_empty());
+}
+
+#[test]
+fn test_treat_ahead_of_main_as_main() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let main = repo.commit("Fix typo");
+    repo.checkout_new_branch("next", main);
+    repo.commit("Add thing");
+    let head = repo.commit("Add other thing");
+    let ahead = ["--treat-ahead-of-main-as-main"];
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.0.0-next.{}", repo.short_id(head))
+    );
+    assert_eq!(repo.version(&ahead), "1.0.1");
+    assert_eq!(
+        repo.version(&[&ahead[..], &["-i", "minor"]].concat()),
+        "1.1.0"
+    );
+
+    repo.checkout_new_branch("diverged", tagged);
+    let head = repo.commit("Add diverging thing");
+    assert_eq!(
+        repo.version(&ahead),
+        format!("1.0.0-diverged.{}", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_remote_tracking_main_branch() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let head = repo.commit("Fix typo");
+
+    // As in a CI clone checking out a detached commit, with main only fetched as a remote-tracking branch.
+    repo.checkout_detached(head);
+    repo.repository
+        .find_reference("refs/heads/main")
+        .unwrap()
+        .delete()
+        .unwrap();
+    repo.repository
+        .reference("refs/remotes/upstream/main", head, false, "fetch")
+        .unwrap();
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.0.0-HEAD.{}", repo.short_id(head))
+    );
+
+    repo.repository
+        .reference("refs/remotes/origin/main", head, false, "fetch")
+        .unwrap();
+    assert_eq!(repo.version(&[]), "1.0.1");
+    let output = repo.run(&["--show-config"]);
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains(r#""main_branch":"main""#));
+}
+
+#[test]
+fn test_main_commit() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    let base = repo.commit("Fix typo");
+    repo.checkout_new_branch("feature", base);
+    let head = repo.commit("Add thing");
+    let prerelease = format!("1.0.0-feature.{}", repo.short_id(head));
+    assert_eq!(repo.version(&[]), prerelease);
+
+    // Standing in for the main branch, a commit HEAD is at or ahead of releases HEAD, wherever the branch is.
+    let at_head = head.to_string();
+    assert_eq!(repo.version(&["--main-commit", &at_head]), "1.0.1");
+    let at_base = base.to_string();
+    assert_eq!(repo.version(&["--main-commit", &at_base]), prerelease);
+    assert_eq!(
+        repo.version(&["--main-commit", &at_base, "--treat-ahead-of-main-as-main"]),
+        "1.0.1"
+    );
+    assert_eq!(
+        repo.version(&["--main-commit", &at_base, "--main-branch", "feature"]),
+        prerelease
+    );
+
+    let blob = repo.repository.blob(b"not a commit").unwrap().to_string();
+    for revision in [
+        "0123456789abcdef0123456789abcdef01234567",
+        "no-such-commit",
+        &blob,
+    ] {
+        let output = repo.run(&["--main-commit", revision]);
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            format!("Error: Error(\"main commit \"{revision}\" does not resolve to a commit in this repository\")\n")
+        );
+    }
+}
@@ -0,0 +1,783 @@
+mod common;
+
+use common::TestRepo;
+use git2::{Signature, Time};
+
+#[test]
+fn test_show_increment_patch() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["--show", "increment"]), "patch");
+}
+
+#[test]
+fn test_show_increment_minor() {
+    let repo = TestRepo::tagged();
+    let base = repo.commit("Prepare");
+    repo.checkout_new_branch("minor/thing", base);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    assert_eq!(repo.version(&["--show", "increment"]), "minor");
+}
+
+#[test]
+fn test_show_increment_major() {
+    let repo = TestRepo::tagged();
+    repo.commit("Remove thing");
+    assert_eq!(
+        repo.version(&["--show", "increment", "--increment", "major"]),
+        "major"
+    );
+}
+
+#[test]
+fn test_show_increment_none() {
+    let repo = TestRepo::tagged();
+    repo.checkout_new_branch("feature", repo.head());
+    repo.commit("Add thing");
+    assert_eq!(repo.version(&["--show", "increment"]), "none");
+}
+
+#[test]
+fn test_format_json() {
+    let repo = TestRepo::tagged();
+    let base = repo.repository.revparse_single("1.2.3").unwrap().id();
+    let head = repo.commit("Fix typo");
+    assert_eq!(
+        repo.version(&["--format", "json"]),
+        format!(
+            r#"{{"version":"1.2.4","increment":"patch","base_commit":"{base}","base_tag":"1.2.3","head_commit":"{head}"}}"#
+        )
+    );
+}
+
+#[test]
+fn test_format_yaml() {
+    let repo = TestRepo::tagged();
+    let base = repo.repository.revparse_single("1.2.3").unwrap().id();
+    let head = repo.commit("Fix typo");
+    let yaml = repo.version(&["--format", "yaml"]);
+    assert_eq!(
+        yaml,
+        format!("version: 1.2.4\nincrement: patch\nbase_commit: {base}\nbase_tag: 1.2.3\nhead_commit: {head}")
+    );
+    assert_eq!(
+        repo.version(&["--preview-all", "--format", "yaml"]),
+        "major: 2.0.0\nminor: 1.3.0\npatch: 1.2.4"
+    );
+}
+
+#[test]
+fn test_format_yaml_quoting() {
+    let repo = TestRepo::tagged();
+    let base = repo.repository.revparse_single("1.2.3").unwrap().id();
+    let numeric = repo.commit("2024");
+    let indicator = repo.commit("- [x] Fix: the #1 typo");
+    let head = repo.commit("no");
+    let yaml = repo.version(&["--format", "yaml", "--changelog", "--counts"]);
+    assert_eq!(
+        yaml,
+        format!(
+            "version: 1.2.4\nincrement: patch\nbase_commit: {base}\nbase_tag: 1.2.3\nhead_commit: {head}\nchangelog:\n- level: none\n  commits:\n  - commit: {head}\n    summary: \"no\"\n  - commit: {indicator}\n    summary: \"- [x] Fix: the #1 typo\"\n  - commit: {numeric}\n    summary: \"2024\"\ncounts:\n  major: 0\n  minor: 0\n  patch: 0\n  skipped: 3"
+        )
+    );
+}
+
+#[test]
+fn test_show_base_commit() {
+    let repo = TestRepo::new();
+    let untagged = repo.commit("Initial commit");
+    assert_eq!(repo.version(&["--show", "base-commit"]), "none");
+    assert!(repo
+        .version(&["--format", "json"])
+        .contains(r#""base_commit":null"#));
+
+    repo.tag_lightweight("1.2.3", untagged);
+    repo.commit("Fix typo");
+    repo.commit("Fix another typo");
+    assert_eq!(
+        repo.version(&["--show", "base-commit"]),
+        untagged.to_string()
+    );
+}
+
+#[test]
+fn test_no_newline() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    assert_eq!(repo.run(&[]).stdout, b"1.2.4\n");
+    assert_eq!(repo.run(&["--no-newline"]).stdout, b"1.2.4");
+    assert_eq!(repo.run(&["-n", "--show", "increment"]).stdout, b"patch");
+}
+
+#[test]
+fn test_build_from_env() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    let output = repo
+        .command(&[
+            "--build-metadata",
+            "build",
+            "--build-from-env",
+            "RUN_NUMBER",
+        ])
+        .env("RUN_NUMBER", "456")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1.2.4+build.456\n"
+    );
+}
+
+#[test]
+fn test_build_from_unset_env() {
+    let repo = TestRepo::tagged();
+    repo.checkout_new_branch("feature", repo.head());
+    let head = repo.commit("Add thing");
+    assert_eq!(
+        repo.version(&["--build-from-env", "RUN_NUMBER"]),
+        format!("1.2.3-feature.{}", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_bulk() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    let second = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.1", second);
+    repo.checkout_new_branch("minor/thing", second);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let third = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    repo.tag_annotated("1.1.0", third);
+    let unrelated = repo.commit("Fix another typo");
+    repo.checkout_detached(first);
+    repo.commit("Go nowhere");
+    repo.tag_lightweight("9.0.0", repo.head());
+    repo.checkout_detached(unrelated);
+
+    assert_eq!(
+        repo.version(&["--bulk"]),
+        [
+            format!(r#"{{"commit":"{first}","next":"0.0.1","tag":"1.0.0"}}"#),
+            format!(r#"{{"commit":"{second}","next":"1.0.1","tag":"1.0.1"}}"#),
+            format!(r#"{{"commit":"{third}","next":"1.1.0","tag":"1.1.0"}}"#),
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_latest_stable() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0-rc.1", first);
+    repo.commit("Fix typo");
+    let output = repo.run(&["--latest-stable"]);
+    assert_eq!(output.status.code(), Some(8));
+    assert!(output.stdout.is_empty());
+
+    let second = repo.commit("Fix another typo");
+    repo.tag_lightweight("1.0.0", second);
+    let third = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0", third);
+    let fourth = repo.commit("Remove thing");
+    repo.tag_lightweight("2.0.0-rc.1", fourth);
+    repo.commit("Fix typo again");
+    assert_eq!(repo.version(&["--latest-stable"]), "1.1.0");
+}
+
+#[test]
+fn test_version_format() {
+    let repo = TestRepo::tagged();
+    let head = repo.commit("Fix typo");
+    let args = |template| ["--build-metadata", "ci.5", "--version-format", template];
+    assert_eq!(
+        repo.version(&args("{major}.{minor}.{patch}{pre}{build}")),
+        "1.2.4+ci.5"
+    );
+    assert_eq!(repo.version(&args("{major}_{minor}")), "1_2");
+    assert_eq!(repo.version(&args("{major}.{minor}.{patch}.0")), "1.2.4.0");
+    assert_eq!(repo.version(&args("v{major}.{minor}.{patch}")), "v1.2.4");
+
+    repo.checkout_new_branch("feature", head);
+    let head = repo.commit("Add thing");
+    assert_eq!(
+        repo.version(&args("{pre}|{build}")),
+        format!("-feature.{}|+ci.5", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_pad() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.4.1", tagged);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&["--pad", "3"]), "001.004.002");
+    assert_eq!(repo.version(&["--pad", "2"]), "01.04.02");
+    assert_eq!(repo.version(&["--pad", "0"]), "1.4.2");
+    assert_eq!(
+        repo.version(&["--pad", "4", "--version-format", "{major}{minor}"]),
+        "00010004"
+    );
+    assert_eq!(
+        repo.version(&["--pad", "3", "--format", "json", "--show", "version"]),
+        format!(
+            r#"{{"version":"1.4.2","increment":"patch","base_commit":"{tagged}","base_tag":"1.4.1","head_commit":"{}"}}"#,
+            repo.head()
+        )
+    );
+}
+
+#[test]
+fn test_color() {
+    let repo = TestRepo::tagged();
+    let never = repo.run(&["--color", "never"]);
+    assert_eq!(
+        String::from_utf8(never.stderr).unwrap(),
+        "Error: Error(\"HEAD already tagged with semver\")\n"
+    );
+    let always = repo.run(&["--color", "always"]);
+    assert_eq!(
+        String::from_utf8(always.stderr).unwrap(),
+        "\x1b[1;31mError:\x1b[0m Error(\"HEAD already tagged with semver\")\n"
+    );
+    let auto = repo.command(&[]).env_remove("NO_COLOR").output().unwrap();
+    assert!(!auto.stderr.contains(&b'\x1b'));
+
+    repo.commit("Fix typo");
+    let always = repo.run(&["--color", "always"]);
+    assert_eq!(always.stdout, b"1.2.4\n");
+}
+
+#[test]
+fn test_preview_all() {
+    let repo = TestRepo::tagged();
+    repo.commit("Fix typo");
+    assert_eq!(
+        repo.version(&["--preview-all"]),
+        "patch 1.2.4\nminor 1.3.0\nmajor 2.0.0"
+    );
+    assert_eq!(
+        repo.version(&["--preview-all", "--format", "json"]),
+        r#"{"major":"2.0.0","minor":"1.3.0","patch":"1.2.4"}"#
+    );
+    assert_eq!(
+        repo.version(&["--preview-all", "--version-format", "v{major}.{minor}"]),
+        "patch v1.2\nminor v1.3\nmajor v2.0"
+    );
+}
+
+#[test]
+fn test_show_prerelease() {
+    let repo = TestRepo::tagged();
+    let main = repo.commit("Fix typo");
+    let output = repo.run(&["--show", "prerelease"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "\n");
+    assert!(repo
+        .run(&["--show", "prerelease", "--no-newline"])
+        .stdout
+        .is_empty());
+
+    repo.checkout_new_branch("feature/thing", main);
+    let head = repo.commit("Add thing");
+    assert_eq!(
+        repo.version(&["--show", "prerelease", "--build-metadata", "ci.5"]),
+        format!("feature-thing.{}", repo.short_id(head))
+    );
+    assert_eq!(
+        repo.version(&[
+            "--show",
+            "prerelease",
+            "--prerelease-format",
+            "{id}.{count}.rc"
+        ]),
+        "feature-thing.2.rc"
+    );
+}
+
+#[test]
+fn test_build_timestamp() {
+    let repo = TestRepo::tagged();
+    // Committed at 2023-11-15 00:13:20 in UTC+2, ie. 2023-11-14 22:13:20 in UTC.
+    let signature =
+        Signature::new("Test", "test@example.com", &Time::new(1_700_000_000, 120)).unwrap();
+    let parent = repo.repository.find_commit(repo.head()).unwrap();
+    let tree = parent.tree().unwrap();
+    repo.repository
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Fix typo",
+            &tree,
+            &[&parent],
+        )
+        .unwrap();
+    assert_eq!(
+        repo.version(&["--build-metadata", "ts.{build_timestamp}"]),
+        "1.2.4+ts.20231114221320"
+    );
+    assert_eq!(
+        repo.version(&["--build-metadata", "{build_timestamp}"]),
+        "1.2.4+20231114221320"
+    );
+}
+
+#[test]
+fn test_build_timestamp_time_source() {
+    let repo = TestRepo::tagged();
+    // Authored at 2023-11-14 22:13:20 in UTC, and committed a day later at 21:13:20 in UTC-1, ie. 22:13:20 in UTC.
+    let author = Signature::new("Test", "test@example.com", &Time::new(1_700_000_000, 0)).unwrap();
+    let committer =
+        Signature::new("Test", "test@example.com", &Time::new(1_700_086_400, -60)).unwrap();
+    let parent = repo.repository.find_commit(repo.head()).unwrap();
+    let tree = parent.tree().unwrap();
+    repo.repository
+        .commit(
+            Some("HEAD"),
+            &author,
+            &committer,
+            "Fix typo",
+            &tree,
+            &[&parent],
+        )
+        .unwrap();
+    assert_eq!(
+        repo.version(&["--build-metadata", "{build_timestamp}"]),
+        "1.2.4+20231115221320"
+    );
+    assert_eq!(
+        repo.version(&[
+            "--build-metadata",
+            "{build_timestamp}",
+            "--time-source",
+            "committer"
+        ]),
+        "1.2.4+20231115221320"
+    );
+    assert_eq!(
+        repo.version(&[
+            "--build-metadata",
+            "{build_timestamp}",
+            "--time-source",
+            "author"
+        ]),
+        "1.2.4+20231114221320"
+    );
+}
+
+#[test]
+fn test_trace_walk() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    let tagged = repo.commit("Fix typo");
+    repo.tag_lightweight("1.2.3", tagged);
+    repo.checkout_new_branch("minor/thing", tagged);
+    let side = repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let fix = repo.commit("Fix another typo");
+    let merge = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+
+    let output = repo.run(&["--trace-walk"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"1.3.0\n");
+    let trace = serde_json::from_slice::<serde_json::Value>(&output.stderr).unwrap();
+    assert_eq!(trace["main_branch"], "main");
+    assert_eq!(trace["head_is_main"], true);
+    assert_eq!(
+        trace["commits"],
+        serde_json::json!([
+            {
+                "commit": merge.to_string(),
+                "parents": [fix.to_string(), side.to_string()],
+                "summary": "Merge branch 'minor/thing'",
+                "level": "minor",
+                "tags": [],
+                "base": false,
+            },
+            {
+                "commit": fix.to_string(),
+                "parents": [tagged.to_string()],
+                "summary": "Fix another typo",
+                "level": null,
+                "tags": [],
+                "base": false,
+            },
+            {
+                "commit": tagged.to_string(),
+                "parents": [repo.repository.find_commit(tagged).unwrap().parent_id(0).unwrap().to_string()],
+                "summary": "Fix typo",
+                "level": null,
+                "tags": ["1.2.3"],
+                "base": true,
+            },
+        ])
+    );
+
+    let file = repo.path().join("trace.json");
+    let output = repo.run(&["--trace-walk", "--trace-file", file.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(file).unwrap()).unwrap(),
+        trace
+    );
+
+    // The dump is written even when the computation then fails, eg. for a tagged HEAD.
+    repo.tag_lightweight("1.3.0", merge);
+    let output = repo.run(&["--trace-walk"]);
+    assert_eq!(output.status.code(), Some(4));
+    let dump = String::from_utf8(output.stderr).unwrap();
+    let trace = serde_json::from_str::<serde_json::Value>(dump.lines().next().unwrap()).unwrap();
+    assert_eq!(trace["commits"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_github_output() {
+    let repo = TestRepo::tagged();
+    let main = repo.commit("Fix typo");
+    let file = repo.path().join("github_output");
+    std::fs::write(&file, "previous=step\n").unwrap();
+    let run = |args: &[&str]| {
+        let output = repo
+            .command(&[&["--github-output"], args].concat())
+            .env("GITHUB_OUTPUT", &file)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(run(&[]), "1.2.4\n");
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "previous=step\nversion=1.2.4\ntag=1.2.4\nis_prerelease=false\nbump=patch\n"
+    );
+
+    std::fs::write(&file, "").unwrap();
+    repo.checkout_new_branch("feature", main);
+    let head = repo.commit("Add thing");
+    run(&["--show", "increment"]);
+    let version = format!("1.2.3-feature.{}", repo.short_id(head));
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        format!("version={version}\ntag={version}\nis_prerelease=true\nbump=none\n")
+    );
+
+    let output = repo
+        .command(&["--github-output"])
+        .env_remove("GITHUB_OUTPUT")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{version}\n")
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Warning: --github-output given but GITHUB_OUTPUT is unset, writing no outputs\n"
+    );
+}
+
+#[test]
+fn test_audit_csv() {
+    let repo = TestRepo::tagged();
+    let tagged = repo.head();
+    let base = repo.commit("Prepare, with \"quotes\"");
+    repo.checkout_new_branch("minor/thing", base);
+    let side = repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let merge = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    let expected = format!(
+        "short_hash,summary,level\n{},Merge branch 'minor/thing',minor\n{},\"Prepare, with \"\"quotes\"\"\",none\n{},Initial commit,none\n",
+        repo.short_id(merge),
+        repo.short_id(base),
+        repo.short_id(tagged)
+    );
+    assert!(!expected.contains(&repo.short_id(side)));
+
+    let output = repo.run(&["--audit-csv"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+
+    let file = repo.path().join("audit.csv");
+    assert_eq!(
+        repo.version(&["--audit-csv", "--audit-file", file.to_str().unwrap()]),
+        "1.3.0"
+    );
+    assert_eq!(std::fs::read_to_string(file).unwrap(), expected);
+}
+
+#[test]
+fn test_only_author() {
+    let repo = TestRepo::tagged();
+    let tagged = repo.head();
+    repo.checkout_new_branch("rc", tagged);
+    repo.commit_by(
+        "Merge branch 'minor/vendored'",
+        "Upstream",
+        "upstream@example.org",
+    );
+    let ours = repo.commit("Merge branch 'patch/ours'");
+    repo.commit_by("Update vendored docs", "Upstream", "upstream@example.org");
+    let head = repo.commit("Tidy up");
+
+    assert_eq!(
+        repo.version(&["--show", "counts"]),
+        "major=0 minor=1 patch=1 skipped=2"
+    );
+    assert_eq!(
+        repo.version(&["--show", "counts", "--only-author", "*@example.com"]),
+        "major=0 minor=0 patch=1 skipped=1"
+    );
+    assert_eq!(
+        repo.version(&["--show", "counts", "--only-author", "Upstream"]),
+        "major=0 minor=1 patch=0 skipped=1"
+    );
+
+    let args = ["--prerelease-format", "{id}.{matched_count}"];
+    assert_eq!(repo.version(&args), "1.2.3-rc.2");
+    assert_eq!(
+        repo.version(&[&args[..], &["--only-author", "test@*"]].concat()),
+        "1.2.3-rc.1"
+    );
+
+    let document = serde_json::from_str::<serde_json::Value>(&repo.version(&[
+        "--format",
+        "json",
+        "--changelog",
+        "--only-author",
+        "*@example.com",
+    ]))
+    .unwrap();
+    assert_eq!(
+        document["changelog"],
+        serde_json::json!([
+            {
+                "level": "patch",
+                "commits": [{"commit": ours.to_string(), "summary": "Merge branch 'patch/ours'"}],
+            },
+            {
+                "level": "none",
+                "commits": [{"commit": head.to_string(), "summary": "Tidy up"}],
+            },
+        ])
+    );
+
+    // The base commit ends the walk whoever authored it.
+    let output = repo.run(&["--audit-csv", "--only-author", "nobody"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "short_hash,summary,level\n{},Initial commit,none\n",
+            repo.short_id(tagged)
+        )
+    );
+}
+
+#[test]
+fn test_changelog() {
+    let repo = TestRepo::tagged();
+    let tagged = repo.head();
+    let base = repo.commit("Prepare");
+    repo.checkout_new_branch("minor/thing", base);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let minor = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    repo.checkout_new_branch("patch/typo", minor);
+    repo.commit("Fix typo");
+    repo.checkout_branch("main");
+    let patch = repo.merge("patch/typo", "Merge branch 'patch/typo'");
+
+    let document = serde_json::from_str::<serde_json::Value>(&repo.version(&[
+        "--format",
+        "json",
+        "--changelog",
+    ]))
+    .unwrap();
+    assert_eq!(
+        document,
+        serde_json::json!({
+            "version": "1.2.4",
+            "increment": "patch",
+            "base_commit": tagged.to_string(),
+            "base_tag": "1.2.3",
+            "head_commit": patch.to_string(),
+            "changelog": [
+                {
+                    "level": "minor",
+                    "commits": [{"commit": minor.to_string(), "summary": "Merge branch 'minor/thing'"}],
+                },
+                {
+                    "level": "patch",
+                    "commits": [{"commit": patch.to_string(), "summary": "Merge branch 'patch/typo'"}],
+                },
+                {
+                    "level": "none",
+                    "commits": [{"commit": base.to_string(), "summary": "Prepare"}],
+                },
+            ],
+        })
+    );
+    assert_eq!(
+        repo.version(&["--format", "yaml", "--changelog"]),
+        format!(
+            "version: 1.2.4\nincrement: patch\nbase_commit: {tagged}\nbase_tag: 1.2.3\nhead_commit: {patch}\nchangelog:\n- level: minor\n  commits:\n  - commit: {minor}\n    summary: Merge branch 'minor/thing'\n- level: patch\n  commits:\n  - commit: {patch}\n    summary: Merge branch 'patch/typo'\n- level: none\n  commits:\n  - commit: {base}\n    summary: Prepare"
+        )
+    );
+    assert_eq!(repo.version(&["--changelog"]), "1.2.4");
+    assert!(!repo.version(&["--format", "json"]).contains("changelog"));
+}
+
+#[test]
+fn test_changelog_since() {
+    let repo = TestRepo::tagged();
+    let tagged = repo.head();
+    repo.checkout_new_branch("minor/thing", tagged);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    let minor = repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    repo.tag_lightweight("1.3.0", minor);
+    let prepare = repo.commit("Prepare");
+    repo.checkout_new_branch("patch/typo", prepare);
+    repo.commit("Fix typo");
+    repo.checkout_branch("main");
+    let patch = repo.merge("patch/typo", "Merge branch 'patch/typo'");
+
+    let changelog = |args: &[&str]| {
+        let document = serde_json::from_str::<serde_json::Value>(
+            &repo.version(&[&["--format", "json", "--changelog"], args].concat()),
+        )
+        .unwrap();
+        assert_eq!(document["version"], "1.3.1");
+        assert_eq!(document["base_tag"], "1.3.0");
+        document["changelog"].clone()
+    };
+    assert_eq!(
+        changelog(&[]),
+        serde_json::json!([
+            {
+                "level": "patch",
+                "commits": [{"commit": patch.to_string(), "summary": "Merge branch 'patch/typo'"}],
+            },
+            {
+                "level": "none",
+                "commits": [{"commit": prepare.to_string(), "summary": "Prepare"}],
+            },
+        ])
+    );
+    assert_eq!(
+        changelog(&["--changelog-since", "1.2.3"]),
+        serde_json::json!([
+            {
+                "level": "minor",
+                "commits": [{"commit": minor.to_string(), "summary": "Merge branch 'minor/thing'"}],
+            },
+            {
+                "level": "patch",
+                "commits": [{"commit": patch.to_string(), "summary": "Merge branch 'patch/typo'"}],
+            },
+            {
+                "level": "none",
+                "commits": [{"commit": prepare.to_string(), "summary": "Prepare"}],
+            },
+        ])
+    );
+    assert_eq!(
+        changelog(&["--changelog-since", "HEAD"]),
+        serde_json::json!([])
+    );
+
+    let output = repo.run(&["--changelog", "--changelog-since", "patch/typo"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Error: Error(\"patch/typo is not a first-parent ancestor of HEAD\")\n"
+    );
+}
+
+#[test]
+fn test_counts() {
+    let repo = TestRepo::tagged();
+    repo.commit("Prepare");
+    for branch in ["major/api", "minor/thing", "minor/other", "patch/typo"] {
+        repo.checkout_new_branch(branch, repo.head());
+        repo.commit("Change things");
+        repo.checkout_branch("main");
+        repo.merge(branch, &format!("Merge branch '{branch}'"));
+    }
+    repo.commit("Tidy up");
+
+    // The commits merged in are not on the first-parent walk, only their merges are.
+    assert_eq!(
+        repo.version(&["--show", "counts"]),
+        "major=1 minor=2 patch=1 skipped=2"
+    );
+    let document =
+        serde_json::from_str::<serde_json::Value>(&repo.version(&["--format", "json", "--counts"]))
+            .unwrap();
+    assert_eq!(
+        document["counts"],
+        serde_json::json!({"major": 1, "minor": 2, "patch": 1, "skipped": 2})
+    );
+    assert!(!repo.version(&["--format", "json"]).contains("counts"));
+    assert!(!repo
+        .version(&["--format", "json", "--show", "counts"])
+        .contains("counts"));
+}
+
+#[test]
+fn test_output_is_stable() {
+    let repo = TestRepo::tagged();
+    let tagged = repo.head();
+    repo.checkout_new_branch("minor/thing", tagged);
+    repo.commit("Add thing");
+    repo.checkout_branch("main");
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    repo.tag_lightweight("1.3.0+b", repo.head());
+    repo.tag_lightweight("1.3.0+a", repo.head());
+    repo.commit("Fix typo");
+
+    for args in [
+        &["--format", "json", "--changelog", "--counts"][..],
+        &["--format", "yaml", "--changelog", "--counts"],
+        &["--preview-all", "--format", "json"],
+        &["--bulk"],
+        &["--trace-walk"],
+    ] {
+        let first = repo.run(args);
+        assert!(first.status.success());
+        let second = repo.run(args);
+        assert_eq!(second.stdout, first.stdout);
+        assert_eq!(second.stderr, first.stderr);
+    }
+
+    // Fields come in declaration order, and the extra sections after them.
+    let json = repo.version(&["--format", "json", "--changelog", "--counts"]);
+    let positions = [
+        "\"version\"",
+        "\"increment\"",
+        "\"base_commit\"",
+        "\"base_tag\"",
+        "\"head_commit\"",
+        "\"changelog\"",
+        "\"counts\"",
+    ]
+    .map(|key| json.find(key).unwrap());
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(json.contains(r#""counts":{"major":0,"minor":0,"patch":0,"skipped":1}"#));
+    assert!(json.contains(r#""base_tag":"1.3.0+b""#));
+    assert_eq!(
+        repo.version(&["--preview-all", "--format", "json"]),
+        r#"{"major":"2.0.0","minor":"1.4.0","patch":"1.3.1"}"#
+    );
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use common::TestRepo;
+use semver::Version;
+
+#[test]
+fn test_prerelease_id_ref() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.checkout_new_branch("feature/thing", tagged);
+    let head = repo.commit("Add thing");
+    repo.checkout_detached(head);
+    let short_id = repo.short_id(head);
+    assert_eq!(repo.version(&[]), format!("1.0.0-HEAD.{short_id}"));
+    assert_eq!(
+        repo.version(&["--prerelease-id-ref", "feature/thing"]),
+        format!("1.0.0-feature-thing.{short_id}")
+    );
+    assert_eq!(
+        repo.version(&["--prerelease-id-ref", "refs/heads/feature/thing"]),
+        format!("1.0.0-feature-thing.{short_id}")
+    );
+    assert_eq!(
+        repo.version(&[
+            "--prerelease-id-ref",
+            "feature/thing",
+            "--prerelease-id",
+            "pr"
+        ]),
+        format!("1.0.0-pr.{short_id}")
+    );
+    assert!(!repo
+        .run(&["--prerelease-id-ref", "missing"])
+        .status
+        .success());
+}
+
+#[test]
+fn test_revision_is_unambiguous_short_id() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.repository
+        .config()
+        .unwrap()
+        .set_i32("core.abbrev", 4)
+        .unwrap();
+
+    // Create side commits until two share the 4 character abbreviation.
+    let signature = TestRepo::signature();
+    let tree = repo.repository.find_commit(tagged).unwrap().tree().unwrap();
+    let parent = repo.repository.find_commit(tagged).unwrap();
+    let mut prefixes = std::collections::HashMap::new();
+    let (first, second) = (0..)
+        .find_map(|n| {
+            let oid = repo
+                .repository
+                .commit(
+                    None,
+                    &signature,
+                    &signature,
+                    &format!("Commit {n}"),
+                    &tree,
+                    &[&parent],
+                )
+                .unwrap();
+            prefixes
+                .insert(oid.to_string()[..4].to_string(), oid)
+                .map(|other| (other, oid))
+        })
+        .unwrap();
+
+    let mut versions = Vec::new();
+    for oid in [first, second] {
+        repo.checkout_detached(oid);
+        let version = repo.version(&[]);
+        let revision = version.rsplit('.').next().unwrap().to_string();
+        assert!(revision.len() > 4, "{revision} is ambiguous");
+        assert!(oid.to_string().starts_with(&revision));
+        versions.push(version);
+    }
+    assert_ne!(versions[0], versions[1]);
+}
+
+#[test]
+fn test_prerelease_format_count() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.checkout_new_branch("feature", tagged);
+    let args = ["--prerelease-format", "{id}.{count}.{revision}"];
+    let first = repo.commit("Add thing");
+    let first_version = Version::parse(&repo.version(&args)).unwrap();
+    assert_eq!(
+        first_version.pre.as_str(),
+        format!("feature.1.{}", repo.short_id(first))
+    );
+    let second = repo.commit("Add other thing");
+    let second_version = Version::parse(&repo.version(&args)).unwrap();
+    assert_eq!(
+        second_version.pre.as_str(),
+        format!("feature.2.{}", repo.short_id(second))
+    );
+    assert!(first_version < second_version);
+
+    assert_eq!(
+        repo.version(&["--prerelease-format", "{id}.{count}"]),
+        "1.0.0-feature.2"
+    );
+    let output = repo.run(&["--prerelease-format", "{id}.{count}!"]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn test_prerelease_format_matched_count() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.checkout_new_branch("rc", tagged);
+    repo.commit("Merge branch 'minor/thing'");
+    repo.commit("Fix typo");
+    repo.commit("Merge branch 'patch/other-thing'");
+    repo.commit("Update docs");
+    repo.commit("Merge branch 'major/another-thing'");
+    let args = ["--prerelease-format", "{id}.{matched_count}.{count}"];
+    assert_eq!(repo.version(&args), "1.0.0-rc.3.5");
+    repo.commit("Fix another typo");
+    assert_eq!(repo.version(&args), "1.0.0-rc.3.6");
+    assert_eq!(
+        repo.version(&[
+            "--prerelease-format",
+            "{id}.{matched_count}",
+            "--match-expression",
+            "^(Fix)"
+        ]),
+        "1.0.0-rc.2"
+    );
+}
+
+#[test]
+fn test_no_slug() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.checkout_new_branch("rc1.2", tagged);
+    let head = repo.commit("Add thing");
+    let short_id = repo.short_id(head);
+    assert_eq!(repo.version(&[]), format!("1.0.0-rc1-2.{short_id}"));
+    assert_eq!(
+        repo.version(&["--no-slug"]),
+        format!("1.0.0-rc1.2.{short_id}")
+    );
+    assert_eq!(
+        repo.version(&["--no-slug", "--prerelease-id", "Beta.3"]),
+        format!("1.0.0-Beta.3.{short_id}")
+    );
+    let output = repo.run(&["--no-slug", "--prerelease-id", "feature/thing"]);
+    assert_eq!(output.status.code(), Some(6));
+}
+
+#[test]
+fn test_relative_to_main() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    let fork = repo.commit("Fix typo");
+    repo.checkout_new_branch("feature", fork);
+    let beta = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0-beta.1", beta);
+    repo.commit("Add other thing");
+    repo.commit("Add another thing");
+
+    let format = ["--prerelease-format", "{id}.{count}"];
+    let relative = [&format[..], &["--relative-to-main"]].concat();
+    assert_eq!(repo.version(&format), "1.1.0-feature.2");
+    assert_eq!(repo.version(&relative), "1.0.0-feature.3");
+    assert_eq!(
+        repo.version(&[&relative[..], &["--show", "base-commit"]].concat()),
+        release.to_string()
+    );
+
+    repo.checkout_branch("main");
+    let patch = repo.commit("Fix another typo");
+    repo.tag_lightweight("1.0.1", patch);
+    repo.checkout_branch("feature");
+    repo.merge("main", "Merge branch 'main' into feature");
+    assert_eq!(repo.version(&format), "1.1.0-feature.3");
+    assert_eq!(repo.version(&relative), "1.0.1-feature.4");
+
+    repo.checkout_branch("main");
+    repo.commit("Fix yet another typo");
+    assert_eq!(repo.version(&relative), "1.0.2");
+}
+
+#[test]
+fn test_prerelease_even_if_tagged() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.2.3", release);
+    let flag = "--prerelease-even-if-tagged";
+    assert_eq!(repo.run(&[]).status.code(), Some(4));
+    assert_eq!(
+        repo.version(&[flag]),
+        format!("1.2.3-main.{}", repo.short_id(release))
+    );
+    assert_eq!(
+        repo.version(&[flag, "--prerelease-id", "rc", "--increment", "major"]),
+        format!("1.2.3-rc.{}", repo.short_id(release))
+    );
+    assert_eq!(repo.run(&[flag, "--next"]).status.code(), Some(2));
+
+    // An untagged HEAD is computed as usual, and a tagged one off the main branch is given its branch's prerelease.
+    let head = repo.commit("Fix typo");
+    assert_eq!(repo.version(&[flag]), "1.2.4");
+    repo.checkout_new_branch("feature", head);
+    let feature = repo.commit("Add thing");
+    repo.tag_lightweight("1.3.0", feature);
+    assert_eq!(
+        repo.version(&[flag]),
+        format!("1.3.0-feature.{}", repo.short_id(feature))
+    );
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use std::path::Path;
+
+use common::TestRepo;
+use tempfile::TempDir;
+
+#[test]
+fn test_git_dir_environment_variable() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.commit("Fix typo");
+
+    let elsewhere = TempDir::new().unwrap();
+    let output = repo
+        .command(&[])
+        .current_dir(elsewhere.path())
+        .env("GIT_DIR", repo.path().join(".git"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.0.1\n");
+}
+
+#[test]
+fn test_repository_flag_precedes_git_dir() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.commit("Fix typo");
+
+    let other = TestRepo::new();
+    let tagged = other.commit("Initial commit");
+    other.tag_lightweight("2.0.0", tagged);
+    other.commit("Fix typo");
+
+    let output = repo
+        .command(&["--repository", other.path().to_str().unwrap()])
+        .env("GIT_DIR", repo.path().join(".git"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2.0.1\n");
+}
+
+#[test]
+fn test_discovery_from_subdirectory() {
+    let repo = TestRepo::new();
+    repo.stage("nested/file.txt", "contents");
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.commit("Fix typo");
+
+    let output = repo
+        .command(&[])
+        .current_dir(repo.path().join("nested"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.0.1\n");
+}
+
+#[test]
+fn test_ceiling_directories() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", tagged);
+    repo.commit("Fix typo");
+    let checkouts = repo.path().join("checkouts");
+    let plain = checkouts.join("plain");
+    std::fs::create_dir_all(&plain).unwrap();
+    let nested = TestRepo::new_in(&checkouts);
+    let nested_tagged = nested.commit("Initial commit");
+    nested.tag_lightweight("5.0.0", nested_tagged);
+    nested.commit("Fix typo");
+
+    let run = |dir: &Path, args: &[&str], ceiling: Option<&Path>| {
+        let mut command = repo.command(args);
+        command.current_dir(dir);
+        if let Some(ceiling) = ceiling {
+            command.env("GIT_CEILING_DIRECTORIES", ceiling);
+        }
+        command.output().unwrap()
+    };
+    // Discovery from within the parent's working tree stops short of the parent once the ceiling is set.
+    let plain_path = plain.to_str().unwrap();
+    for args in [&[][..], &["--repository", plain_path]] {
+        let output = run(&plain, args, None);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.0.1\n");
+
+        let output = run(&plain, args, Some(&checkouts));
+        assert_eq!(output.status.code(), Some(3));
+        assert!(output.stdout.is_empty());
+    }
+
+    // A repository nested below the ceiling is its own, with or without it.
+    for ceiling in [None, Some(checkouts.as_path())] {
+        let output = run(nested.path(), &[], ceiling);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "5.0.1\n");
+    }
+}
+
+#[test]
+fn test_global_config_untouched() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    repo.version(&[]);
+    assert!(!repo.home.path().join(".gitconfig").exists());
+}
+
+#[test]
+fn test_trust_repo_leaves_global_config_untouched() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    assert_eq!(repo.version(&["--trust-repo"]), "0.0.1");
+    assert!(!repo.home.path().join(".gitconfig").exists());
+}
+
+#[test]
+fn test_show_config() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    let other = TestRepo::new();
+    other.commit("Initial commit");
+
+    let output = repo
+        .command(&[
+            "--show-config",
+            "--repository",
+            other.path().to_str().unwrap(),
+            "--main-branch",
+            "trunk",
+            "--main-branch",
+            "main",
+        ])
+        .env("GIT_DIR", repo.path().join(".git"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let config: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(
+        std::fs::canonicalize(config["repository"].as_str().unwrap()).unwrap(),
+        std::fs::canonicalize(other.path()).unwrap()
+    );
+    assert_eq!(config["main_branch"], "main");
+    assert_eq!(
+        config["options"]["main_branch"],
+        serde_json::json!(["trunk", "main"])
+    );
+    assert_eq!(config["options"]["default_increment"], "patch");
+    assert_eq!(config["options"]["increment"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_unborn_branch() {
+    let repo = TestRepo::new();
+    assert_eq!(
+        repo.error(&[]),
+        "Error: Error(\"HEAD is on branch main, which has no commits yet, commit something to compute a version\")\n"
+    );
+    assert!(repo
+        .error(&["list"])
+        .contains("HEAD is on branch main, which has no commits yet"));
+
+    // A deleted branch is indistinguishable from one yet to be born.
+    let first = repo.commit("Initial commit");
+    repo.checkout_new_branch("feature", first);
+    repo.repository
+        .find_reference("refs/heads/feature")
+        .unwrap()
+        .delete()
+        .unwrap();
+    assert!(repo
+        .error(&[])
+        .contains("HEAD is on branch feature, which has no commits yet"));
+}
+
+#[test]
+fn test_detached_head_not_commit() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    let blob = repo.repository.blob(b"not a commit").unwrap();
+    std::fs::write(repo.path().join(".git/HEAD"), format!("{blob}\n")).unwrap();
+    let output = repo.run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Error: Error(\"HEAD is detached at {blob}, which is not a commit in this repository\")\n")
+    );
+}
+
+#[test]
+fn test_corrupt_head() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    std::fs::write(repo.path().join(".git/HEAD"), "ref: refs/heads/ma..in\n").unwrap();
+    let output = repo.run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("HEAD cannot be read, the repository may be corrupt"));
+}
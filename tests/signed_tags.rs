@@ -0,0 +1,96 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::TestRepo;
+use git2::Oid;
+
+/// Stands in for gpg, accepting any signature but those reading "bad", and logging each run to a file beside it.
+const FAKE_GPG: &str = r#"#!/bin/sh
+echo run >> "$0.log"
+cat > /dev/null
+while [ "$1" != --verify ]; do shift; done
+if grep -q bad "$2"; then
+    echo "[GNUPG:] NEWSIG"
+    echo "[GNUPG:] BADSIG 0123456789ABCDEF Test <test@example.com>"
+    exit 1
+fi
+echo "[GNUPG:] NEWSIG"
+echo "[GNUPG:] GOODSIG 0123456789ABCDEF Test <test@example.com>"
+echo "[GNUPG:] VALIDSIG 0123456789ABCDEF0123456789ABCDEF01234567 2024-01-01 1704067200 0 4 0 1 10 00 0123456789ABCDEF0123456789ABCDEF01234567"
+echo "[GNUPG:] TRUST_ULTIMATE 0 pgp"
+"#;
+
+fn tag_signed(repo: &TestRepo, name: &str, target: Oid, signature: &str) {
+    let object = repo.repository.find_object(target, None).unwrap();
+    let message = format!(
+        "{name}\n-----BEGIN PGP SIGNATURE-----\n\n{signature}\n-----END PGP SIGNATURE-----\n"
+    );
+    repo.repository
+        .tag(name, &object, &TestRepo::signature(), &message, false)
+        .unwrap();
+}
+
+/// Install the fake gpg as the repository's gpg program, returning the path of its log.
+fn fake_gpg(repo: &TestRepo) -> std::path::PathBuf {
+    let gpg = repo.home.path().join("gpg");
+    std::fs::write(&gpg, FAKE_GPG).unwrap();
+    std::fs::set_permissions(&gpg, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = repo.repository.config().unwrap();
+    config
+        .set_str("gpg.program", gpg.to_str().unwrap())
+        .unwrap();
+    gpg.with_extension("log")
+}
+
+#[test]
+fn test_require_signed_tags() {
+    let repo = TestRepo::new();
+    fake_gpg(&repo);
+
+    let signed = repo.commit("Initial commit");
+    tag_signed(&repo, "1.0.0", signed, "good");
+    let annotated = repo.commit("Add thing");
+    repo.tag_annotated("1.1.0", annotated);
+    let lightweight = repo.commit("Add other thing");
+    repo.tag_lightweight("1.2.0", lightweight);
+    let forged = repo.commit("Add another thing");
+    tag_signed(&repo, "1.3.0", forged, "bad");
+    repo.commit("Fix typo");
+
+    assert_eq!(repo.version(&[]), "1.3.1");
+    assert_eq!(repo.version(&["--require-signed-tags"]), "1.0.1");
+    assert_eq!(repo.version(&["list", "--require-signed-tags"]), "1.0.0");
+}
+
+#[test]
+fn test_require_signed_tags_verifies_each_tag_once() {
+    let repo = TestRepo::new();
+    let log = fake_gpg(&repo);
+
+    let signed = repo.commit("Initial commit");
+    tag_signed(&repo, "1.0.0", signed, "good");
+    let lightweight = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0", lightweight);
+    let forged = repo.commit("Add other thing");
+    tag_signed(&repo, "1.2.0", forged, "bad");
+    repo.commit("Fix typo");
+
+    assert_eq!(
+        repo.version(&[
+            "--require-signed-tags",
+            "--changelog",
+            "--counts",
+            "--check",
+            "--trace-walk",
+            "--trace-file",
+            repo.home.path().join("trace.json").to_str().unwrap(),
+        ])
+        .lines()
+        .next(),
+        Some("1.0.1")
+    );
+    assert_eq!(std::fs::read_to_string(log).unwrap().lines().count(), 2);
+}
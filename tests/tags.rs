@@ -0,0 +1,399 @@
+mod common;
+
+use common::TestRepo;
+
+#[test]
+fn test_build_metadata_tag_is_base() {
+    let repo = TestRepo::new();
+    let tagged = repo.commit("Initial commit");
+    repo.tag_lightweight("1.2.3+ci.5", tagged);
+    let main = repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.2.4");
+
+    repo.checkout_new_branch("feature", main);
+    let head = repo.commit("Add feature");
+    assert_eq!(
+        repo.version(&[]),
+        format!("1.2.3-feature.{}", repo.short_id(head))
+    );
+}
+
+#[test]
+fn test_stable_base_skips_prerelease_tags() {
+    let repo = TestRepo::new();
+    let stable = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", stable);
+    let prerelease = repo.commit("Add thing");
+    repo.tag_lightweight("2.0.0-rc.1", prerelease);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "2.0.1");
+    assert_eq!(repo.version(&["--base", "any"]), "2.0.1");
+    assert_eq!(repo.version(&["--base", "stable"]), "1.0.1");
+}
+
+#[test]
+fn test_stable_base_ignores_prerelease_tag_on_head() {
+    let repo = TestRepo::new();
+    let stable = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", stable);
+    let head = repo.commit("Add thing");
+    repo.tag_lightweight("1.0.1-rc.1", head);
+    assert!(repo.error(&[]).contains("HEAD already tagged with semver"));
+    assert_eq!(repo.version(&["--base", "stable"]), "1.0.1");
+}
+
+#[test]
+fn test_exclude_tag_pattern() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    let nightly = repo.commit("Add thing");
+    repo.tag_lightweight("9.9.9-nightly", nightly);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "9.9.10");
+    assert_eq!(
+        repo.version(&["--exclude-tag-pattern", "*-nightly"]),
+        "1.0.1"
+    );
+    assert_eq!(
+        repo.version(&[
+            "--exclude-tag-pattern",
+            "*-rc*",
+            "--exclude-tag-pattern",
+            "*-nightly"
+        ]),
+        "1.0.1"
+    );
+}
+
+#[test]
+fn test_unreachable_tag_is_ignored() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    repo.checkout_new_branch("other", release);
+    let other = repo.commit("Add thing");
+    repo.tag_lightweight("5.0.0", other);
+    repo.checkout_branch("main");
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.0.1");
+}
+
+#[test]
+fn test_merged_side_branch_tag_is_ignored() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    repo.checkout_new_branch("minor/thing", release);
+    let side = repo.commit("Add thing");
+    repo.tag_lightweight("5.0.0", side);
+    repo.checkout_branch("main");
+    let mainline = repo.commit("Fix typo");
+    repo.tag_lightweight("1.1.0", mainline);
+    repo.merge("minor/thing", "Merge branch 'minor/thing'");
+    assert_eq!(repo.version(&[]), "1.2.0");
+    assert_eq!(
+        repo.version(&["--show", "base-commit"]),
+        mainline.to_string()
+    );
+}
+
+#[test]
+fn test_greatest_tag_on_commit_is_base() {
+    for names in [["1.9.9", "2.0.0-rc.1"], ["2.0.0-rc.1", "1.9.9"]] {
+        let repo = TestRepo::new();
+        let tagged = repo.commit("Initial commit");
+        for name in names {
+            repo.tag_lightweight(name, tagged);
+        }
+        repo.commit("Fix typo");
+        assert_eq!(repo.version(&[]), "2.0.1");
+        assert_eq!(repo.version(&["--base", "stable"]), "1.9.10");
+        assert_eq!(repo.version(&["--exclude-tag-pattern", "2.*"]), "1.9.10");
+    }
+}
+
+#[test]
+fn test_tags_of_non_commits_are_ignored() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    let blob = repo.repository.blob(b"not a commit").unwrap();
+    repo.tag_lightweight("2.0.0", blob);
+    repo.tag_annotated("3.0.0", blob);
+    let tree = repo.repository.find_commit(release).unwrap().tree_id();
+    repo.tag_annotated("4.0.0", tree);
+    repo.commit("Fix typo");
+    assert_eq!(repo.version(&[]), "1.0.1");
+    assert_eq!(repo.version(&["list"]), "1.0.0");
+}
+
+#[test]
+fn test_tag_template_at_delimited() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    repo.tag_lightweight("packages/api@1.2.3", first);
+    repo.tag_lightweight("packages/web@5.0.0", first);
+    repo.commit("Fix typo");
+    let api = [
+        "--tag-template",
+        "{component}@{version}",
+        "--component",
+        "packages/api",
+    ];
+    assert_eq!(repo.version(&api), "1.2.4");
+    assert_eq!(repo.version(&[&["list"], &api[..]].concat()), "1.2.3");
+
+    assert_eq!(repo.version(&[&["tag"], &api[..]].concat()), "1.2.4");
+    let tagged = repo
+        .repository
+        .find_reference("refs/tags/packages/api@1.2.4")
+        .unwrap()
+        .target()
+        .unwrap();
+    assert_eq!(tagged, repo.head());
+}
+
+#[test]
+fn test_tag_template_slash_delimited() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("api/v1.2.3", first);
+    repo.tag_lightweight("web/v5.0.0", first);
+    repo.tag_lightweight("api/v1.2", first);
+    repo.commit("Fix typo");
+    let api = [
+        "--tag-template",
+        "{component}/v{version}",
+        "--component",
+        "api",
+    ];
+    assert_eq!(repo.version(&api), "1.2.4");
+    assert_eq!(
+        repo.version(&[&api[..], &["--exec", "echo {tag}"]].concat()),
+        "1.2.4\napi/v1.2.4"
+    );
+}
+
+#[test]
+fn test_invalid_tag_template() {
+    let repo = TestRepo::new();
+    repo.commit("Initial commit");
+    for args in [
+        &["--tag-template", "{component}@{version}"][..],
+        &["--tag-template", "v{major}"],
+        &["--tag-template", "{version}-{version}"],
+    ] {
+        let output = repo.run(args);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("must have the version placeholder exactly once"));
+    }
+}
+
+#[test]
+fn test_version_tagged_elsewhere() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    repo.checkout_new_branch("other", release);
+    let other = repo.commit("Fix typo elsewhere");
+    repo.tag_lightweight("1.0.1", other);
+    repo.checkout_branch("main");
+    repo.commit("Fix typo");
+
+    let output = repo.run(&[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1.0.1\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Warning: version 1.0.1 is already tagged on commit {other}\n")
+    );
+    assert!(repo.run(&["--quiet"]).stderr.is_empty());
+
+    let output = repo.run(&["--strict"]);
+    assert_eq!(output.status.code(), Some(11));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Error: version 1.0.1 is already tagged on commit {other}\n")
+    );
+    assert_eq!(repo.run(&["tag", "--strict"]).status.code(), Some(11));
+    assert_eq!(repo.version(&["list"]), "1.0.0");
+    let output = repo.run(&["--build-metadata", "ci.5", "--strict"]);
+    assert_eq!(output.status.code(), Some(11));
+    assert_eq!(repo.version(&["--strict", "-i", "minor"]), "1.1.0");
+}
+
+#[test]
+fn test_show_base_tag() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    assert_eq!(repo.version(&["--show", "base-tag"]), "none");
+
+    repo.tag_lightweight("v1.2.3", first);
+    repo.tag_lightweight("api@2.0.0", first);
+    let second = repo.commit("Fix typo");
+    repo.commit("Fix another typo");
+    let prefixed = ["--tag-template", "v{version}"];
+    assert_eq!(
+        repo.version(&[&prefixed[..], &["--show", "base-tag"]].concat()),
+        "v1.2.3"
+    );
+    assert!(repo
+        .version(&[&prefixed[..], &["--format", "json"]].concat())
+        .contains(r#""base_tag":"v1.2.3""#));
+    assert_eq!(
+        repo.version(&[
+            "--tag-template",
+            "{component}@{version}",
+            "--component",
+            "api",
+            "--show",
+            "base-tag"
+        ]),
+        "api@2.0.0"
+    );
+    assert_eq!(repo.version(&["--show", "base-tag"]), "none");
+
+    let from = [&prefixed[..], &["--show", "base-tag", "--from"]].concat();
+    assert_eq!(repo.version(&[&from[..], &["v1.2.3"]].concat()), "v1.2.3");
+    assert_eq!(
+        repo.version(&[&from[..], &[second.to_string().as_str()]].concat()),
+        "none"
+    );
+}
+
+#[test]
+fn test_equal_versions_ordered_by_name() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0+a", first);
+    let second = repo.commit("Fix typo");
+    repo.tag_lightweight("1.0.0+b", second);
+    repo.tag_lightweight("1.0.0+c", second);
+    repo.commit("Fix another typo");
+    assert_eq!(repo.version(&[]), "1.0.1");
+    assert_eq!(repo.version(&["--show", "base-tag"]), "1.0.0+c");
+
+    let lines = repo.version(&["--bulk"]);
+    let tags = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["tag"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(tags, ["1.0.0+a", "1.0.0+b", "1.0.0+c"]);
+}
+
+#[test]
+fn test_lightweight_and_annotated_tags() {
+    let repo = TestRepo::new();
+    let first = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", first);
+    let second = repo.commit("Add thing");
+    repo.tag_annotated("1.1.0", second);
+    let third = repo.commit("Add another thing");
+    // An annotated tag of an annotated tag, peeling to the commit through both tag objects.
+    repo.tag_annotated("tag-of-third", third);
+    let inner = repo
+        .repository
+        .find_reference("refs/tags/tag-of-third")
+        .unwrap()
+        .target()
+        .unwrap();
+    repo.tag_annotated("1.2.0", inner);
+    repo.commit("Fix typo");
+
+    assert_eq!(repo.version(&["list"]), "1.0.0\n1.1.0\n1.2.0");
+    assert_eq!(repo.version(&[]), "1.2.1");
+    assert_eq!(repo.version(&["--show", "base-commit"]), third.to_string());
+    assert_eq!(
+        repo.version(&["--exclude-tag-pattern", "1.2.*", "--show", "base-commit"]),
+        second.to_string()
+    );
+    assert_eq!(
+        repo.version(&[
+            "--exclude-tag-pattern",
+            "1.1.*",
+            "--exclude-tag-pattern",
+            "1.2.*",
+            "--show",
+            "base-commit"
+        ]),
+        first.to_string()
+    );
+}
+
+#[test]
+fn test_dangling_tag_is_ignored() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    repo.commit("Fix typo");
+    std::fs::write(
+        repo.path().join(".git/refs/tags/2.0.0"),
+        "0123456789abcdef0123456789abcdef01234567\n",
+    )
+    .unwrap();
+    assert_eq!(repo.version(&[]), "1.0.1");
+    assert_eq!(repo.version(&["list"]), "1.0.0");
+}
+
+#[test]
+fn test_prerelease_shadows_release() {
+    let repo = TestRepo::new();
+    let release = repo.commit("Initial commit");
+    repo.tag_lightweight("1.0.0", release);
+    let candidate = repo.commit("Add thing");
+    repo.tag_lightweight("1.1.0-rc.1", candidate);
+    let released = repo.commit("Fix typo");
+    repo.tag_lightweight("1.1.0", released);
+
+    repo.checkout_new_branch("fresh", released);
+    repo.commit("Add fresh thing");
+    let output = repo.run(&["--strict"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    repo.checkout_new_branch("feature", candidate);
+    let head = repo.commit("Add other thing");
+    let version = format!("1.1.0-feature.{}", repo.short_id(head));
+    let output = repo.run(&[]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{version}\n")
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Warning: prerelease {version} orders below release 1.1.0 already tagged on commit {released}, the branch is likely behind the release line\n")
+    );
+    assert!(repo.run(&["--quiet"]).stderr.is_empty());
+
+    let output = repo.run(&["--strict"]);
+    assert_eq!(output.status.code(), Some(14));
+    assert!(output.stdout.is_empty());
+    assert_eq!(repo.run(&["tag", "--strict"]).status.code(), Some(14));
+    assert_eq!(repo.version(&["list"]), "1.0.0\n1.1.0-rc.1");
+}
+
+#[test]
+fn test_build_metadata_tag_on_head() {
+    let repo = TestRepo::new();
+    let head = repo.commit("Initial commit");
+    repo.tag_lightweight("1.2.3+build.9", head);
+    assert!(repo.error(&[]).contains("HEAD already tagged with semver"));
+    assert_eq!(repo.run(&["--check"]).status.code(), Some(10));
+    assert_eq!(repo.version(&["list"]), "1.2.3");
+    assert_eq!(repo.version(&["--latest-stable"]), "1.2.3");
+    assert_eq!(repo.version(&["--next"]), "1.2.4");
+    assert_eq!(
+        repo.version(&["--next", "--show", "base-tag"]),
+        "1.2.3+build.9"
+    );
+    assert_eq!(
+        repo.version(&["--next", "--build-metadata", "build.10"]),
+        "1.2.4+build.10"
+    );
+}